@@ -0,0 +1,6 @@
+DROP TABLE events;
+DROP TABLE sites_locations;
+DROP TABLE sites_config;
+DROP TABLE surfaces;
+DROP TABLE locations;
+DROP TABLE provinces;
@@ -0,0 +1,101 @@
+-- The tables the scraper, the api and the venue import use, as they were before the
+-- later migrations. IF NOT EXISTS lets databases that predate the migrations, where
+-- the tables were made by hand, take this migration as already applied.
+
+CREATE TABLE IF NOT EXISTS provinces (
+    id INT NOT NULL,
+    province_name VARCHAR(32) NOT NULL,
+    country VARCHAR(32) NOT NULL,
+    PRIMARY KEY (id)
+);
+
+CREATE TABLE IF NOT EXISTS locations (
+    id INT NOT NULL,
+    address1 TEXT NULL,
+    address2 TEXT NULL,
+    city VARCHAR(32) NULL,
+    name VARCHAR(64) NOT NULL,
+    uuid VARCHAR(128) NULL,
+    recording_hours_local VARCHAR(32) NULL,
+    postal_code VARCHAR(32) NULL,
+    all_sheets_count INT NULL,
+    longitude FLOAT NULL,
+    latitude FLOAT NULL,
+    logo_url TEXT NULL,
+    province_id INT NULL,
+    venue_status VARCHAR(11) NULL,
+    zone VARCHAR(32) NULL,
+    total_surfaces INT NULL,
+    deleted_at TIMESTAMP NULL,
+    PRIMARY KEY (id),
+    CONSTRAINT fk_locations_province FOREIGN KEY (province_id) REFERENCES provinces (id)
+);
+
+CREATE TABLE IF NOT EXISTS surfaces (
+    id INT NOT NULL,
+    location_id INT NOT NULL,
+    name VARCHAR(64) NOT NULL,
+    uuid VARCHAR(128) NOT NULL,
+    orderIndex INT NOT NULL,
+    venue_id INT NOT NULL,
+    closed_from BIGINT UNSIGNED NOT NULL DEFAULT 0,
+    coming_soon BOOLEAN NOT NULL DEFAULT FALSE,
+    online BOOLEAN NOT NULL DEFAULT FALSE,
+    status VARCHAR(32) NOT NULL,
+    sports VARCHAR(32) NOT NULL,
+    first_media_date BIGINT UNSIGNED NOT NULL DEFAULT 0,
+    deleted_at TIMESTAMP NULL,
+    PRIMARY KEY (id),
+    CONSTRAINT fk_surfaces_location FOREIGN KEY (location_id) REFERENCES locations (id)
+);
+
+CREATE TABLE IF NOT EXISTS sites_config (
+    id INT NOT NULL AUTO_INCREMENT,
+    site_name VARCHAR(100) NOT NULL,
+    display_name VARCHAR(200) NULL,
+    base_url VARCHAR(500) NOT NULL,
+    home_team VARCHAR(100) NULL,
+    parser_config JSON NULL,
+    enabled BOOLEAN NULL DEFAULT TRUE,
+    last_scraped_at TIMESTAMP NULL,
+    scrape_frequency_hours INT NULL DEFAULT 24,
+    notes TEXT NULL,
+    created_at TIMESTAMP NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    parser_type VARCHAR(64) NOT NULL,
+    PRIMARY KEY (id),
+    UNIQUE KEY uq_sites_config_site_name (site_name)
+);
+
+-- the venue a site's location text maps to, one row per site and text
+CREATE TABLE IF NOT EXISTS sites_locations (
+    site VARCHAR(64) NOT NULL,
+    location VARCHAR(128) NOT NULL,
+    location_id INT NULL,
+    loc VARCHAR(64) NULL,
+    surface VARCHAR(64) NULL,
+    address VARCHAR(128) NULL,
+    match_type VARCHAR(32) NULL,
+    surface_id INT NOT NULL DEFAULT 0,
+    PRIMARY KEY (site, location)
+);
+
+-- a game is imported once per site, later scrapes of it are ignored
+CREATE TABLE IF NOT EXISTS events (
+    id BIGINT UNSIGNED NOT NULL AUTO_INCREMENT,
+    site VARCHAR(64) NOT NULL,
+    source_type VARCHAR(64) NULL,
+    datetime DATETIME NOT NULL,
+    home_team VARCHAR(128) NOT NULL,
+    oid_home VARCHAR(128) NULL,
+    guest_team VARCHAR(128) NOT NULL,
+    oid_guest VARCHAR(128) NULL,
+    location VARCHAR(128) NULL,
+    division VARCHAR(128) NULL,
+    location_id INT NULL,
+    surface_id INT NOT NULL DEFAULT 0,
+    date_created TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    UNIQUE KEY uq_events_game (site, datetime, home_team, guest_team),
+    INDEX idx_events_datetime (datetime)
+);
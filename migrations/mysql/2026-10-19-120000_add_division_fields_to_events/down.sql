@@ -0,0 +1,6 @@
+ALTER TABLE events
+    DROP INDEX idx_events_division,
+    DROP COLUMN age_group,
+    DROP COLUMN tier,
+    DROP COLUMN gender,
+    DROP COLUMN season_type;
@@ -0,0 +1,6 @@
+ALTER TABLE events
+    ADD COLUMN age_group VARCHAR(16) NULL,
+    ADD COLUMN tier VARCHAR(16) NULL,
+    ADD COLUMN gender VARCHAR(16) NULL,
+    ADD COLUMN season_type VARCHAR(16) NULL,
+    ADD INDEX idx_events_division (age_group, tier, gender);
@@ -0,0 +1,3 @@
+ALTER TABLE events
+    DROP INDEX idx_events_canonical_id,
+    DROP COLUMN canonical_id;
@@ -0,0 +1,3 @@
+ALTER TABLE events
+    ADD COLUMN canonical_id BIGINT UNSIGNED NULL,
+    ADD INDEX idx_events_canonical_id (canonical_id);
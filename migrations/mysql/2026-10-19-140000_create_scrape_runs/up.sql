@@ -0,0 +1,13 @@
+CREATE TABLE scrape_runs (
+    id BIGINT UNSIGNED NOT NULL AUTO_INCREMENT,
+    site VARCHAR(100) NOT NULL,
+    started_at DATETIME NOT NULL,
+    finished_at DATETIME NOT NULL,
+    games_found INT NOT NULL DEFAULT 0,
+    parse_errors INT NOT NULL DEFAULT 0,
+    status VARCHAR(16) NOT NULL,
+    error TEXT NULL,
+    alert TEXT NULL,
+    PRIMARY KEY (id),
+    INDEX idx_scrape_runs_site (site, started_at)
+);
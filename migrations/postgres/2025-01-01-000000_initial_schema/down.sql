@@ -0,0 +1,7 @@
+DROP TABLE scrape_runs;
+DROP TABLE events;
+DROP TABLE sites_locations;
+DROP TABLE sites_config;
+DROP TABLE surfaces;
+DROP TABLE locations;
+DROP TABLE provinces;
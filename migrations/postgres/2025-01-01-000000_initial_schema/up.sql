@@ -0,0 +1,110 @@
+-- The tables of the MySQL production schema that the scraper, the api and the venue
+-- import use. BIGINT UNSIGNED columns are BIGINT here.
+
+CREATE TABLE provinces (
+    id INTEGER PRIMARY KEY,
+    province_name VARCHAR(32) NOT NULL,
+    country VARCHAR(32) NOT NULL
+);
+
+CREATE TABLE locations (
+    id INTEGER PRIMARY KEY,
+    address1 TEXT NULL,
+    address2 TEXT NULL,
+    city VARCHAR(32) NULL,
+    name VARCHAR(64) NOT NULL,
+    uuid VARCHAR(128) NULL,
+    recording_hours_local VARCHAR(32) NULL,
+    postal_code VARCHAR(32) NULL,
+    all_sheets_count INTEGER NULL,
+    longitude REAL NULL,
+    latitude REAL NULL,
+    logo_url TEXT NULL,
+    province_id INTEGER NULL REFERENCES provinces (id),
+    venue_status VARCHAR(11) NULL,
+    zone VARCHAR(32) NULL,
+    total_surfaces INTEGER NULL,
+    deleted_at TIMESTAMP NULL
+);
+
+CREATE TABLE surfaces (
+    id INTEGER PRIMARY KEY,
+    location_id INTEGER NOT NULL REFERENCES locations (id),
+    name VARCHAR(64) NOT NULL,
+    uuid VARCHAR(128) NOT NULL,
+    "orderIndex" INTEGER NOT NULL,
+    venue_id INTEGER NOT NULL,
+    closed_from BIGINT NOT NULL DEFAULT 0,
+    coming_soon BOOLEAN NOT NULL DEFAULT FALSE,
+    online BOOLEAN NOT NULL DEFAULT FALSE,
+    status VARCHAR(32) NOT NULL,
+    sports VARCHAR(32) NOT NULL,
+    first_media_date BIGINT NOT NULL DEFAULT 0,
+    deleted_at TIMESTAMP NULL
+);
+
+CREATE TABLE sites_config (
+    id SERIAL PRIMARY KEY,
+    site_name VARCHAR(100) NOT NULL UNIQUE,
+    display_name VARCHAR(200) NULL,
+    base_url VARCHAR(500) NOT NULL,
+    home_team VARCHAR(100) NULL,
+    parser_config JSON NULL,
+    enabled BOOLEAN NULL DEFAULT TRUE,
+    last_scraped_at TIMESTAMP NULL,
+    scrape_frequency_hours INTEGER NULL DEFAULT 24,
+    notes TEXT NULL,
+    created_at TIMESTAMP NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NULL DEFAULT CURRENT_TIMESTAMP,
+    parser_type VARCHAR(64) NOT NULL
+);
+
+CREATE TABLE sites_locations (
+    site VARCHAR(64) NOT NULL,
+    location VARCHAR(128) NOT NULL,
+    location_id INTEGER NULL,
+    loc VARCHAR(64) NULL,
+    surface VARCHAR(64) NULL,
+    address VARCHAR(128) NULL,
+    match_type VARCHAR(32) NULL,
+    surface_id INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (site, location)
+);
+
+CREATE TABLE events (
+    id BIGSERIAL PRIMARY KEY,
+    site VARCHAR(64) NOT NULL,
+    source_type VARCHAR(64) NULL,
+    datetime TIMESTAMP NOT NULL,
+    home_team VARCHAR(128) NOT NULL,
+    oid_home VARCHAR(128) NULL,
+    guest_team VARCHAR(128) NOT NULL,
+    oid_guest VARCHAR(128) NULL,
+    location VARCHAR(128) NULL,
+    division VARCHAR(128) NULL,
+    location_id INTEGER NULL,
+    surface_id INTEGER NOT NULL DEFAULT 0,
+    date_created TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    age_group VARCHAR(16) NULL,
+    tier VARCHAR(16) NULL,
+    gender VARCHAR(16) NULL,
+    season_type VARCHAR(16) NULL,
+    canonical_id BIGINT NULL,
+    UNIQUE (site, datetime, home_team, guest_team)
+);
+CREATE INDEX idx_events_datetime ON events (datetime);
+CREATE INDEX idx_events_division ON events (age_group, tier, gender);
+CREATE INDEX idx_events_canonical_id ON events (canonical_id);
+
+CREATE TABLE scrape_runs (
+    id BIGSERIAL PRIMARY KEY,
+    site VARCHAR(100) NOT NULL,
+    started_at TIMESTAMP NOT NULL,
+    finished_at TIMESTAMP NOT NULL,
+    games_found INTEGER NOT NULL DEFAULT 0,
+    parse_errors INTEGER NOT NULL DEFAULT 0,
+    status VARCHAR(16) NOT NULL,
+    error TEXT NULL,
+    alert TEXT NULL
+);
+CREATE INDEX idx_scrape_runs_site ON scrape_runs (site, started_at);
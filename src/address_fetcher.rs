@@ -1,13 +1,16 @@
-use crate::client::{HttpClient, Response};
+use crate::client::Response;
+use crate::fetcher::SharedFetcher;
+use crate::metrics::{self, METRICS};
 use anyhow::{Context, Result};
 use dashmap::DashMap;
 use scraper::{Html, Selector};
 use std::sync::Arc;
 use std::sync::LazyLock;
 use tokio::sync::RwLock;
+use tracing::{debug, info};
 
 pub struct AddressFetcher {
-    client: Arc<HttpClient>,
+    client: SharedFetcher,
     addresses: Arc<DashMap<String, Arc<RwLock<Address>>>>,
 }
 
@@ -28,7 +31,7 @@ static ADDRESS_SELECTOR: LazyLock<Selector> =
     LazyLock::new(|| Selector::parse("div.bg_primary > div > div > div > h2 > small").unwrap());
 
 impl AddressFetcher {
-    pub fn new(client: Arc<HttpClient>) -> Self {
+    pub fn new(client: SharedFetcher) -> Self {
         Self {
             client: client,
             addresses: Arc::new(DashMap::new()),
@@ -36,7 +39,7 @@ impl AddressFetcher {
     }
 
     pub fn total_addresses(&self) {
-        println!("total addresses: {}", self.addresses.clone().len());
+        info!(addresses = self.addresses.len(), "address cache");
     }
 
     pub async fn get_address(&self, _site: &str, base_url: &str, url: &str) -> Result<String> {
@@ -49,7 +52,8 @@ impl AddressFetcher {
             // Fast path: check read lock first
             let r = current_addr.read().await;
             if r.status == AddressStatus::Ready {
-                println!("cache hit");
+                debug!(url, "address cache hit");
+                METRICS.address_lookup(true);
                 return Ok(r.address.clone());
             }
             drop(r);
@@ -59,10 +63,16 @@ impl AddressFetcher {
 
             // Double-check after acquiring write lock
             if lock.status == AddressStatus::Ready {
-                println!("cache hit");
+                debug!(url, "address cache hit");
+                METRICS.address_lookup(true);
                 return Ok(lock.address.clone());
             }
 
+            // a redirect is part of the same lookup
+            if Arc::ptr_eq(&current_addr, &orig_addr) {
+                METRICS.address_lookup(false);
+            }
+
             // Fetch URL while holding write lock
             match self.client.get(&url).await? {
                 Response::Content(contents) => {
@@ -88,9 +98,13 @@ impl AddressFetcher {
                 }
                 Response::Redirect(redirect) => {
                     if redirect.contains("/Human/") {
+                        METRICS
+                            .captcha_hits
+                            .with_label_values(&[&metrics::host_label(&url)])
+                            .inc();
                         return Err(anyhow::anyhow!("captcha presented for {}", url));
                     }
-                    println!("redirect {}", redirect);
+                    debug!(url, redirect, "venue redirect");
                     // Drop current lock before acquiring new one
                     drop(lock);
 
@@ -0,0 +1,241 @@
+// Admin endpoints for managing sites_config, the same operations as the sites binary.
+// Every request needs an X-Api-Key header matching API_KEY from the config.
+//
+// Adding a site runs a dry-run scrape of the current month first and only saves the row
+// when the scrape succeeds. With `?dry_run=true` the preview is returned without saving.
+
+use super::{ApiError, Repo, blocking};
+use crate::models::{NewSitesConfig, SitesConfig, SitesConfigChanges};
+use crate::site_admin;
+use crate::site_scraper::{ScrapedGame, Scraper};
+use axum::extract::rejection::{JsonRejection, QueryRejection};
+use axum::extract::{Path, Query, Request, State};
+use axum::http::StatusCode;
+use axum::middleware::{self, Next};
+use axum::response::Response;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+#[derive(Clone)]
+pub struct AdminState {
+    pub repo: Repo,
+    pub scraper: Arc<Scraper>,
+    pub api_key: String,
+}
+
+pub fn router(state: AdminState) -> Router {
+    Router::new()
+        .route("/admin/sites", get(list_sites).post(add_site))
+        .route("/admin/sites/{name}", get(show_site).put(update_site))
+        .route("/admin/sites/{name}/enable", post(enable_site))
+        .route("/admin/sites/{name}/disable", post(disable_site))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            require_api_key,
+        ))
+        .with_state(state)
+}
+
+async fn require_api_key(
+    State(state): State<AdminState>,
+    req: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let key = req
+        .headers()
+        .get(API_KEY_HEADER)
+        .and_then(|v| v.to_str().ok());
+    // an empty configured key disables the admin api rather than accepting an empty header
+    match key {
+        Some(key) if !state.api_key.is_empty() && key_matches(key, &state.api_key) => {
+            Ok(next.run(req).await)
+        }
+        _ => Err(ApiError::new(StatusCode::UNAUTHORIZED, "invalid api key")),
+    }
+}
+
+// `==` on strings returns at the first differing byte, which lets a client find the key
+// byte by byte from response times. Both keys are MACed to the same length and compared
+// by `verify_slice` in constant time.
+fn key_matches(key: &str, expected: &str) -> bool {
+    let mac = |k: &str| {
+        let mut mac = Hmac::<sha2::Sha256>::new_from_slice(b"admin api key")
+            .expect("hmac takes any key size");
+        mac.update(k.as_bytes());
+        mac
+    };
+    mac(expected)
+        .verify_slice(&mac(key).finalize().into_bytes())
+        .is_ok()
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct AddQuery {
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AddResponse {
+    pub saved: bool,
+    pub site: SitesConfig,
+    pub games_found: usize,
+    /// the first games of the dry run, by date
+    pub preview: Vec<PreviewGame>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PreviewGame {
+    pub date: chrono::NaiveDateTime,
+    pub home_team: String,
+    pub away_team: String,
+    pub location: String,
+    pub division: String,
+}
+
+impl From<&ScrapedGame> for PreviewGame {
+    fn from(g: &ScrapedGame) -> Self {
+        PreviewGame {
+            date: g.date,
+            home_team: g.home_team.clone(),
+            away_team: g.away_team.clone(),
+            location: g.location.clone(),
+            division: g.division.clone(),
+        }
+    }
+}
+
+async fn list_sites(State(state): State<AdminState>) -> Result<Json<Vec<SitesConfig>>, ApiError> {
+    let sites = blocking(&state.repo, |r| r.list_sites()).await?;
+    Ok(Json(sites))
+}
+
+async fn show_site(
+    State(state): State<AdminState>,
+    Path(name): Path<String>,
+) -> Result<Json<SitesConfig>, ApiError> {
+    Ok(Json(get_site(&state.repo, name).await?))
+}
+
+async fn add_site(
+    State(state): State<AdminState>,
+    query: Result<Query<AddQuery>, QueryRejection>,
+    body: Result<Json<NewSitesConfig>, JsonRejection>,
+) -> Result<(StatusCode, Json<AddResponse>), ApiError> {
+    let Query(query) = query?;
+    let Json(mut site) = body.map_err(|e| ApiError::bad_request(e.body_text()))?;
+    site_admin::validate_new_site(&mut site).map_err(|e| ApiError::bad_request(e.to_string()))?;
+
+    let name = site.site_name.clone();
+    if blocking(&state.repo, move |r| r.get_site(&name))
+        .await?
+        .is_some()
+    {
+        return Err(ApiError::new(
+            StatusCode::CONFLICT,
+            format!("site {} already exists", site.site_name),
+        ));
+    }
+
+    let config = site_admin::unsaved_config(&site);
+    let games = site_admin::dry_run(&state.scraper, &config)
+        .await
+        .map_err(|e| {
+            ApiError::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("dry run failed: {:#}", e),
+            )
+        })?;
+    let preview = games
+        .iter()
+        .take(site_admin::PREVIEW_GAMES)
+        .map(PreviewGame::from)
+        .collect();
+
+    if query.dry_run {
+        let res = AddResponse {
+            saved: false,
+            site: config,
+            games_found: games.len(),
+            preview,
+        };
+        return Ok((StatusCode::OK, Json(res)));
+    }
+
+    let name = site.site_name.clone();
+    let saved = blocking(&state.repo, move |r| {
+        r.insert_site(&site)?;
+        r.get_site(&name)
+    })
+    .await?
+    .ok_or_else(|| anyhow::anyhow!("site missing after insert"))?;
+
+    let res = AddResponse {
+        saved: true,
+        site: saved,
+        games_found: games.len(),
+        preview,
+    };
+    Ok((StatusCode::CREATED, Json(res)))
+}
+
+async fn update_site(
+    State(state): State<AdminState>,
+    Path(name): Path<String>,
+    body: Result<Json<SitesConfigChanges>, JsonRejection>,
+) -> Result<Json<SitesConfig>, ApiError> {
+    let Json(mut changes) = body.map_err(|e| ApiError::bad_request(e.body_text()))?;
+    site_admin::validate_changes(&mut changes).map_err(|e| ApiError::bad_request(e.to_string()))?;
+    save_changes(&state.repo, name, changes).await
+}
+
+async fn enable_site(
+    State(state): State<AdminState>,
+    Path(name): Path<String>,
+) -> Result<Json<SitesConfig>, ApiError> {
+    let changes = SitesConfigChanges {
+        enabled: Some(true),
+        ..Default::default()
+    };
+    save_changes(&state.repo, name, changes).await
+}
+
+async fn disable_site(
+    State(state): State<AdminState>,
+    Path(name): Path<String>,
+) -> Result<Json<SitesConfig>, ApiError> {
+    let changes = SitesConfigChanges {
+        enabled: Some(false),
+        ..Default::default()
+    };
+    save_changes(&state.repo, name, changes).await
+}
+
+async fn save_changes(
+    repo: &Repo,
+    name: String,
+    changes: SitesConfigChanges,
+) -> Result<Json<SitesConfig>, ApiError> {
+    let site = name.clone();
+    let found = blocking(repo, move |r| r.update_site(&site, &changes)).await?;
+    if !found {
+        return Err(not_found(&name));
+    }
+    Ok(Json(get_site(repo, name).await?))
+}
+
+async fn get_site(repo: &Repo, name: String) -> Result<SitesConfig, ApiError> {
+    let site = name.clone();
+    blocking(repo, move |r| r.get_site(&site))
+        .await?
+        .ok_or_else(|| not_found(&name))
+}
+
+fn not_found(name: &str) -> ApiError {
+    ApiError::new(StatusCode::NOT_FOUND, format!("site {} not found", name))
+}
@@ -0,0 +1,184 @@
+// Calendar feeds for subscribing to a site, a venue, a single surface or a team. Clients
+// poll feeds often, so each response carries an ETag of its body and a matching
+// If-None-Match gets a 304 without the calendar.
+
+use super::{ApiError, Repo};
+use crate::ics;
+use crate::repository::{EventFilter, EventQueries};
+use axum::Router;
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use sha2::{Digest, Sha256};
+
+/// how far back feeds go, calendar apps keep past events they already have
+pub const FEED_PAST_DAYS: i64 = 30;
+pub const FEED_MAX_EVENTS: i64 = 2000;
+
+pub fn routes() -> Router<Repo> {
+    Router::new()
+        .route("/feeds/sites/{site}", get(site_feed))
+        .route("/feeds/locations/{location_id}", get(location_feed))
+        .route("/feeds/surfaces/{surface_id}", get(surface_feed))
+        .route("/feeds/teams/{team}", get(team_feed))
+}
+
+async fn site_feed(
+    State(repo): State<Repo>,
+    Path(site): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let site = strip_ics(&site).to_string();
+    let filter = EventFilter {
+        site: Some(site.clone()),
+        ..feed_filter()
+    };
+    feed(&repo, site, filter, &headers).await
+}
+
+async fn location_feed(
+    State(repo): State<Repo>,
+    Path(location_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let location_id = parse_id(&location_id)?;
+    let filter = EventFilter {
+        location_id: Some(location_id),
+        ..feed_filter()
+    };
+    feed(&repo, format!("location {}", location_id), filter, &headers).await
+}
+
+async fn surface_feed(
+    State(repo): State<Repo>,
+    Path(surface_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let surface_id = parse_id(&surface_id)?;
+    let filter = EventFilter {
+        surface_id: Some(surface_id),
+        ..feed_filter()
+    };
+    feed(&repo, format!("surface {}", surface_id), filter, &headers).await
+}
+
+async fn team_feed(
+    State(repo): State<Repo>,
+    Path(team): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let team = strip_ics(&team).trim().to_string();
+    if team.len() < 3 {
+        return Err(ApiError::bad_request("team must be at least 3 characters"));
+    }
+    let filter = EventFilter {
+        team: Some(team.clone()),
+        ..feed_filter()
+    };
+    feed(&repo, team, filter, &headers).await
+}
+
+async fn feed(
+    repo: &Repo,
+    name: String,
+    filter: EventFilter,
+    headers: &HeaderMap,
+) -> Result<Response, ApiError> {
+    let events = repo.find_events_with_venue(filter, FEED_MAX_EVENTS).await?;
+
+    let events: Vec<ics::CalendarEvent> = events.iter().map(ics::CalendarEvent::from).collect();
+    let mut body = Vec::new();
+    ics::write_calendar(&name, &events, &mut body)?;
+
+    let etag = etag(&body);
+    let mut res = if if_none_match(headers, &etag) {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        (
+            [(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("text/calendar; charset=utf-8"),
+            )],
+            body,
+        )
+            .into_response()
+    };
+    res.headers_mut().insert(
+        header::ETAG,
+        HeaderValue::from_str(&etag).map_err(anyhow::Error::from)?,
+    );
+    res.headers_mut().insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static("public, max-age=900"),
+    );
+    Ok(res)
+}
+
+fn feed_filter() -> EventFilter {
+    let from = chrono::prelude::Local::now().naive_local() - chrono::Duration::days(FEED_PAST_DAYS);
+    EventFilter {
+        from: Some(from.date().and_hms_opt(0, 0, 0).unwrap()),
+        ..Default::default()
+    }
+}
+
+// subscription urls usually end in .ics, the suffix is optional
+fn strip_ics(s: &str) -> &str {
+    s.strip_suffix(".ics").unwrap_or(s)
+}
+
+fn parse_id(s: &str) -> Result<i32, ApiError> {
+    strip_ics(s)
+        .parse()
+        .map_err(|_| ApiError::bad_request(format!("invalid id {}", s)))
+}
+
+pub fn etag(body: &[u8]) -> String {
+    // a hash with a fixed definition, so the tag of a feed stays the same across restarts
+    // and builds and clients keep their cached copy
+    let hash: String = Sha256::digest(body)[..8]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    format!("\"{}\"", hash)
+}
+
+fn if_none_match(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(|t| t.trim().trim_start_matches("W/"))
+        .any(|t| t == etag || t == "*")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_if_none_match() {
+        let tag = etag(b"BEGIN:VCALENDAR");
+        // the first 8 bytes of the SHA-256, the same in every build
+        assert_eq!("\"9daa8e24f9b75907\"", tag);
+        assert_ne!(tag, etag(b"BEGIN:VCALENDAR\r\n"));
+
+        let mut headers = HeaderMap::new();
+        assert!(!if_none_match(&headers, &tag));
+
+        headers.insert(
+            header::IF_NONE_MATCH,
+            HeaderValue::from_str(&format!("\"other\", W/{}", tag)).unwrap(),
+        );
+        assert!(if_none_match(&headers, &tag));
+    }
+
+    #[test]
+    fn test_parse_id() {
+        assert_eq!(42, parse_id("42.ics").unwrap());
+        assert_eq!(42, parse_id("42").unwrap());
+        assert!(parse_id("arena.ics").is_err());
+    }
+}
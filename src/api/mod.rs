@@ -0,0 +1,339 @@
+// Read-only JSON API over the scraped events and the venue tables. Queries run on the
+// blocking pool since the repository uses synchronous diesel connections.
+//
+// Paging parameters are extracted as their own `Query` rather than flattened into the
+// filter structs, since serde_urlencoded cannot parse numbers inside flattened structs.
+
+use crate::Repository;
+use crate::db::DbConnection;
+use crate::repository::{CountBy, EventCount, EventFilter, EventQueries, Page};
+use axum::extract::rejection::QueryRejection;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+pub mod admin;
+pub mod feeds;
+
+pub const DEFAULT_PER_PAGE: i64 = 50;
+pub const MAX_PER_PAGE: i64 = 500;
+
+pub type Repo = Arc<Repository<DbConnection>>;
+
+pub fn router(repo: Repo) -> Router {
+    Router::new()
+        .route("/events", get(list_events))
+        .route("/events/counts", get(count_events))
+        .route("/sites", get(list_sites))
+        .route("/locations", get(list_locations))
+        .route("/surfaces", get(list_surfaces))
+        .route("/sites_locations", get(list_sites_locations))
+        .merge(feeds::routes())
+        .with_state(repo)
+}
+
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        ApiError {
+            status,
+            message: message.into(),
+        }
+    }
+
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        ApiError::new(StatusCode::BAD_REQUEST, message)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = serde_json::json!({ "error": self.message });
+        (self.status, Json(body)).into_response()
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        tracing::error!("api error: {:#}", e);
+        ApiError {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            message: "internal error".into(),
+        }
+    }
+}
+
+impl From<QueryRejection> for ApiError {
+    fn from(e: QueryRejection) -> Self {
+        ApiError::bad_request(e.body_text())
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PageResponse<T> {
+    pub data: Vec<T>,
+    pub page: i64,
+    pub per_page: i64,
+    pub total: i64,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct PageQuery {
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+impl PageQuery {
+    pub fn validate(&self) -> Result<Page, ApiError> {
+        let page = self.page.unwrap_or(1);
+        let per_page = self.per_page.unwrap_or(DEFAULT_PER_PAGE);
+        if page < 1 {
+            return Err(ApiError::bad_request("page must be 1 or more"));
+        }
+        if !(1..=MAX_PER_PAGE).contains(&per_page) {
+            return Err(ApiError::bad_request(format!(
+                "per_page must be between 1 and {}",
+                MAX_PER_PAGE
+            )));
+        }
+        Ok(Page { page, per_page })
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct EventsQuery {
+    pub site: Option<String>,
+    /// YYYY-MM-DD, inclusive
+    pub from: Option<String>,
+    /// YYYY-MM-DD, inclusive
+    pub to: Option<String>,
+    pub division: Option<String>,
+    pub age_group: Option<String>,
+    pub tier: Option<String>,
+    pub gender: Option<String>,
+    pub team: Option<String>,
+    pub location_id: Option<i32>,
+    pub surface_id: Option<i32>,
+    #[serde(default)]
+    pub include_duplicates: bool,
+}
+
+impl EventsQuery {
+    pub fn validate(&self) -> Result<EventFilter, ApiError> {
+        let from = parse_date("from", &self.from)?.map(|d| d.and_hms_opt(0, 0, 0).unwrap());
+        let to = parse_date("to", &self.to)?.map(|d| d.and_hms_opt(23, 59, 59).unwrap());
+        if let (Some(from), Some(to)) = (from, to)
+            && from > to
+        {
+            return Err(ApiError::bad_request("from must not be after to"));
+        }
+        if let Some(g) = &self.gender
+            && g != "female"
+            && g != "male"
+        {
+            return Err(ApiError::bad_request("gender must be female or male"));
+        }
+
+        let filter = EventFilter {
+            site: non_empty(&self.site),
+            from,
+            to,
+            division: non_empty(&self.division),
+            age_group: non_empty(&self.age_group).map(|a| a.to_uppercase()),
+            tier: non_empty(&self.tier).map(|t| t.to_uppercase()),
+            gender: non_empty(&self.gender),
+            team: non_empty(&self.team),
+            location_id: self.location_id,
+            surface_id: self.surface_id,
+            include_duplicates: self.include_duplicates,
+        };
+        Ok(filter)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CountsQuery {
+    pub by: CountBy,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SurfacesQuery {
+    pub location_id: Option<i32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SitesLocationsQuery {
+    pub site: Option<String>,
+}
+
+fn parse_date(name: &str, value: &Option<String>) -> Result<Option<chrono::NaiveDate>, ApiError> {
+    match non_empty(value) {
+        Some(v) => chrono::NaiveDate::parse_from_str(&v, "%Y-%m-%d")
+            .map(Some)
+            .map_err(|_| ApiError::bad_request(format!("{} must be a date as YYYY-MM-DD", name))),
+        _ => Ok(None),
+    }
+}
+
+fn non_empty(value: &Option<String>) -> Option<String> {
+    value
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(String::from)
+}
+
+/// Runs a blocking repository call off the async runtime.
+pub async fn blocking<T, F>(repo: &Repo, f: F) -> Result<T, ApiError>
+where
+    T: Send + 'static,
+    F: FnOnce(&Repository<DbConnection>) -> anyhow::Result<T> + Send + 'static,
+{
+    Ok(repo.blocking(f).await?)
+}
+
+async fn list_events(
+    State(repo): State<Repo>,
+    query: Result<Query<EventsQuery>, QueryRejection>,
+    page: Result<Query<PageQuery>, QueryRejection>,
+) -> Result<Json<PageResponse<crate::models::Event>>, ApiError> {
+    let filter = query?.validate()?;
+    let page = page?.validate()?;
+    let (data, total) = repo.find_events(filter, page).await?;
+    Ok(Json(PageResponse {
+        data,
+        page: page.page,
+        per_page: page.per_page,
+        total,
+    }))
+}
+
+/// Events matching the same filters as /events, counted per site, division, age group,
+/// location or surface.
+async fn count_events(
+    State(repo): State<Repo>,
+    query: Result<Query<EventsQuery>, QueryRejection>,
+    counts: Result<Query<CountsQuery>, QueryRejection>,
+) -> Result<Json<Vec<EventCount>>, ApiError> {
+    let filter = query?.validate()?;
+    let by = counts?.by;
+    Ok(Json(repo.count_events(filter, by).await?))
+}
+
+async fn list_sites(
+    State(repo): State<Repo>,
+) -> Result<Json<Vec<crate::models::SitesConfig>>, ApiError> {
+    let sites = blocking(&repo, |r| r.list_sites()).await?;
+    Ok(Json(sites))
+}
+
+async fn list_locations(
+    State(repo): State<Repo>,
+    query: Result<Query<PageQuery>, QueryRejection>,
+) -> Result<Json<PageResponse<crate::models::LocationRow>>, ApiError> {
+    let page = query?.validate()?;
+    let (data, total) = blocking(&repo, move |r| r.list_locations(page)).await?;
+    Ok(Json(PageResponse {
+        data,
+        page: page.page,
+        per_page: page.per_page,
+        total,
+    }))
+}
+
+async fn list_surfaces(
+    State(repo): State<Repo>,
+    query: Result<Query<SurfacesQuery>, QueryRejection>,
+    page: Result<Query<PageQuery>, QueryRejection>,
+) -> Result<Json<PageResponse<crate::models::SurfaceRow>>, ApiError> {
+    let page = page?.validate()?;
+    let location_id = query?.location_id;
+    let (data, total) = blocking(&repo, move |r| r.list_surfaces(location_id, page)).await?;
+    Ok(Json(PageResponse {
+        data,
+        page: page.page,
+        per_page: page.per_page,
+        total,
+    }))
+}
+
+async fn list_sites_locations(
+    State(repo): State<Repo>,
+    query: Result<Query<SitesLocationsQuery>, QueryRejection>,
+    page: Result<Query<PageQuery>, QueryRejection>,
+) -> Result<Json<PageResponse<crate::models::SitesLocationRow>>, ApiError> {
+    let page = page?.validate()?;
+    let site = non_empty(&query?.site);
+    let (data, total) = blocking(&repo, move |r| {
+        r.list_sites_locations(site.as_deref(), page)
+    })
+    .await?;
+    Ok(Json(PageResponse {
+        data,
+        page: page.page,
+        per_page: page.per_page,
+        total,
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_events_query_validate() {
+        let q = EventsQuery {
+            site: Some("whitby".into()),
+            from: Some("2025-11-01".into()),
+            to: Some("2025-11-30".into()),
+            tier: Some("aa".into()),
+            team: Some(" ".into()),
+            ..Default::default()
+        };
+        let filter = q.validate().unwrap();
+        assert_eq!(Some("whitby".into()), filter.site);
+        assert_eq!("2025-11-30 23:59:59", filter.to.unwrap().to_string());
+        assert_eq!(Some("AA".into()), filter.tier);
+        assert_eq!(None, filter.team);
+
+        let page = PageQuery::default().validate().unwrap();
+        assert_eq!((1, DEFAULT_PER_PAGE), (page.page, page.per_page));
+    }
+
+    #[test]
+    fn test_events_query_rejects_invalid() {
+        let bad = [
+            EventsQuery {
+                from: Some("11/01/2025".into()),
+                ..Default::default()
+            },
+            EventsQuery {
+                from: Some("2025-12-01".into()),
+                to: Some("2025-11-01".into()),
+                ..Default::default()
+            },
+            EventsQuery {
+                gender: Some("boys".into()),
+                ..Default::default()
+            },
+        ];
+        for q in bad {
+            let err = q.validate().unwrap_err();
+            assert_eq!(StatusCode::BAD_REQUEST, err.status);
+        }
+
+        for (page, per_page) in [(Some(0), None), (None, Some(MAX_PER_PAGE + 1))] {
+            let err = PageQuery { page, per_page }.validate().unwrap_err();
+            assert_eq!(StatusCode::BAD_REQUEST, err.status);
+        }
+    }
+}
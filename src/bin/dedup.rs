@@ -0,0 +1,76 @@
+use calendar_scraper::Repository;
+use calendar_scraper::config;
+use calendar_scraper::db::DbConnection;
+use calendar_scraper::dedup;
+use calendar_scraper::logging;
+use clap::Parser;
+
+#[derive(Parser, Debug)]
+#[command(name = "dedup")]
+struct Args {
+    /// first day to check, defaults to today
+    #[arg(short, long)]
+    from: Option<String>,
+    #[arg(short, long, default_value_t = 30)]
+    days: i64,
+    #[arg(short, long, default_value_t = 15)]
+    window_minutes: i64,
+    /// comma separated sites whose fields win, most preferred first
+    #[arg(short, long)]
+    prefer: Option<String>,
+    #[arg(long)]
+    dry_run: bool,
+    /// log as JSON lines on stderr
+    #[arg(long)]
+    log_json: bool,
+    #[command(flatten)]
+    config: config::ConfigArgs,
+}
+
+fn main() {
+    let args = Args::parse();
+    logging::init(args.log_json);
+    let cfg = config::load(&args.config).unwrap_or_else(|e| fail(format!("{:#}", e)));
+
+    let from = match args.from {
+        Some(ymd) => chrono::NaiveDate::parse_from_str(&ymd, "%Y-%m-%d").unwrap(),
+        _ => chrono::prelude::Local::now().naive_local().date(),
+    };
+    let from = from.and_hms_opt(0, 0, 0).unwrap();
+    let to = from + chrono::Duration::days(args.days);
+
+    let repo =
+        Repository::<DbConnection>::new(&cfg.db_dsn).unwrap_or_else(|e| fail(format!("{:#}", e)));
+    let events = repo
+        .get_events_between(from, to)
+        .unwrap_or_else(|e| fail(format!("failed to load events: {:#}", e)));
+
+    let opts = dedup::DedupOptions {
+        window: chrono::Duration::minutes(args.window_minutes),
+        site_priority: args
+            .prefer
+            .map(|p| p.split(",").map(String::from).collect())
+            .unwrap_or_default(),
+    };
+    let groups = dedup::find_duplicates(&events, &opts);
+
+    for g in groups.iter() {
+        println!("{} <- {:?}", g.canonical_id, g.duplicate_ids);
+    }
+    println!(
+        "{} events checked, {} duplicate groups",
+        events.len(),
+        groups.len()
+    );
+
+    if !args.dry_run
+        && let Err(e) = repo.link_duplicates(&groups)
+    {
+        fail(format!("failed to link duplicates: {:#}", e));
+    }
+}
+
+fn fail(e: impl std::fmt::Display) -> ! {
+    tracing::error!("{}", e);
+    std::process::exit(1);
+}
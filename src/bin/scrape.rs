@@ -3,48 +3,240 @@ use calendar_scraper::address_fetcher;
 use calendar_scraper::client;
 use calendar_scraper::cmdutils;
 use calendar_scraper::config;
+use calendar_scraper::db::{self, DbConnection};
+use calendar_scraper::division;
+use calendar_scraper::fetcher;
+use calendar_scraper::health;
+use calendar_scraper::logging;
+use calendar_scraper::metrics::METRICS;
 use calendar_scraper::models;
-use calendar_scraper::repository::RepositoryOps;
+use calendar_scraper::notify;
+use calendar_scraper::repository::{self, RepositoryOps};
+use calendar_scraper::saved_pages;
+use calendar_scraper::schedule_diff;
+use calendar_scraper::site_admin;
 use calendar_scraper::site_scraper;
-use clap::Parser;
-use diesel::prelude::MysqlConnection;
+use calendar_scraper::vcr;
+use clap::{Parser, Subcommand};
+use diesel::Connection;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use tracing::{Instrument, debug, error, info, info_span, warn};
 
 #[derive(Parser, Debug)]
-#[command(name = "scrape")]
+#[command(name = "scrape", subcommand_negates_reqs = true)]
 struct Args {
-    #[arg(short, long)]
-    sites: String,
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[arg(short, long, required = true)]
+    sites: Option<String>,
     #[arg(short, long)]
     date: Option<String>,
     #[arg(short, long)]
     import_locations: bool,
-    #[arg(long)]
-    out_file: String,
+    #[arg(long, required = true)]
+    out_file: Option<String>,
     #[arg(long)]
     import_events: bool,
+    /// make the stored events of the scraped month match the scrape instead of only
+    /// adding games: changed games are updated and games no longer listed are marked
+    /// deleted. Implies --import-events
+    #[arg(long)]
+    sync: bool,
+    /// with --sync, the share of a site's stored games that may be cancelled before the
+    /// scrape is taken for truncated and not imported
+    #[arg(long, default_value_t = repository::DEFAULT_MAX_CANCELLED_SHARE)]
+    max_cancelled_share: f64,
+    #[arg(long, value_enum, default_value_t = cmdutils::OutputFormat::Csv)]
+    format: cmdutils::OutputFormat,
+    /// only write games where this team plays, e.g. for a per-team calendar
+    #[arg(long)]
+    team: Option<String>,
+    /// write all sites to a single file instead of one file per site, implied by "-"
+    #[arg(long)]
+    combined: bool,
+    /// save every HTTP request and response to this directory
+    #[arg(long, conflicts_with = "replay")]
+    record: Option<PathBuf>,
+    /// answer HTTP requests from a directory made with --record instead of the network
+    #[arg(long)]
+    replay: Option<PathBuf>,
+    /// write parser health alerts to this file as JSON
+    #[arg(long)]
+    health_report: Option<PathBuf>,
+    /// post parser health alerts to this url
+    #[arg(long)]
+    alert_webhook: Option<String>,
+    /// write Prometheus metrics of the run to this file, e.g. for node_exporter's
+    /// textfile collector
+    #[arg(long)]
+    metrics_file: Option<PathBuf>,
+    /// log as JSON lines on stderr
+    #[arg(long, global = true)]
+    log_json: bool,
+    #[command(flatten)]
+    config: config::ConfigArgs,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// scrape one month of a site that is not in sites_config and print the games,
+    /// without a database
+    Preview {
+        #[arg(long)]
+        url: String,
+        #[arg(long, default_value = "calendar")]
+        parser: String,
+        /// YYYY-MM, defaults to the current month
+        #[arg(short, long)]
+        date: Option<String>,
+        #[arg(long, value_enum, default_value_t = cmdutils::OutputFormat::Csv)]
+        format: cmdutils::OutputFormat,
+    },
+    /// parse saved schedule pages instead of fetching them, venue links are resolved
+    /// against saved venue pages, see `saved_pages::saved_page_name` for the file names
+    Offline {
+        /// saved schedule pages, when none are given the page for --url and --date is
+        /// read from --pages
+        files: Vec<PathBuf>,
+        /// base url of the site, needed for relative venue links
+        #[arg(long)]
+        url: Option<String>,
+        /// directory of saved pages, defaults to the directory of the first file
+        #[arg(long)]
+        pages: Option<PathBuf>,
+        #[arg(long, default_value = "calendar")]
+        parser: String,
+        /// YYYY-MM, month based pages only show the day
+        #[arg(short, long)]
+        date: Option<String>,
+        #[arg(long, value_enum, default_value_t = cmdutils::OutputFormat::Csv)]
+        format: cmdutils::OutputFormat,
+    },
+    /// save a real schedule page as a parser test fixture, trimmed to its day blocks;
+    /// the page is a saved page or a recording made with --record
+    Fixture {
+        file: PathBuf,
+        /// file name of the fixture, without .html
+        #[arg(long)]
+        name: String,
+        #[arg(long, default_value = "calendar")]
+        parser: String,
+        /// YYYY-MM the page is for, month based pages only show the day
+        #[arg(short, long)]
+        date: Option<String>,
+        #[arg(long, default_value = "tests/fixtures")]
+        out_dir: PathBuf,
+    },
+    /// create or update the database schema with the migrations built into the binary
+    Migrate {
+        /// only list the migrations that would run
+        #[arg(long)]
+        pending: bool,
+    },
 }
 
 #[tokio::main]
 async fn main() {
-    let cfg = config::load();
+    let args = Args::parse();
+    logging::init(args.log_json);
 
-    println!("{:?}", cfg);
+    match args.command {
+        Some(Command::Preview {
+            url,
+            parser,
+            date,
+            format,
+        }) => {
+            preview(url, parser, date, format).await;
+            return;
+        }
+        Some(Command::Offline {
+            files,
+            url,
+            pages,
+            parser,
+            date,
+            format,
+        }) => {
+            offline(files, url, pages, parser, date, format).await;
+            return;
+        }
+        Some(Command::Fixture {
+            file,
+            name,
+            parser,
+            date,
+            out_dir,
+        }) => {
+            fixture(&file, &name, &parser, date, &out_dir);
+            return;
+        }
+        Some(Command::Migrate { pending }) => {
+            let cfg = config::load(&args.config).unwrap_or_else(|e| fail(format!("{:#}", e)));
+            migrate(&cfg, pending);
+            return;
+        }
+        None => {}
+    }
 
-    let args = Args::parse();
-    println!("{:?}", args);
+    let cfg = config::load(&args.config).unwrap_or_else(|e| fail(format!("{:#}", e)));
+    debug!(?cfg, ?args, "starting");
+
+    scrape(args, cfg).await;
+}
+
+fn migrate(cfg: &config::AppConfig, pending_only: bool) {
+    let mut conn = DbConnection::establish(&cfg.db_dsn)
+        .unwrap_or_else(|e| fail(format!("failed to connect to the database: {}", e)));
+    let res = if pending_only {
+        db::pending_migrations(&mut conn)
+    } else {
+        db::run_migrations(&mut conn)
+    };
+    let names = res.unwrap_or_else(|e| fail(format!("{:#}", e)));
+
+    if names.is_empty() {
+        info!("the database schema is up to date");
+    }
+    for name in names {
+        if pending_only {
+            println!("{}", name);
+        } else {
+            info!(migration = name, "applied");
+        }
+    }
+}
 
+#[tracing::instrument(name = "run", skip_all, fields(sites = args.sites.as_deref().unwrap()))]
+async fn scrape(args: Args, cfg: config::AppConfig) {
     let dt = match args.date {
         Some(ymd) => chrono::NaiveDate::parse_from_str(&ymd, "%Y-%m-%d").unwrap(),
         _ => chrono::prelude::Local::now().naive_local().date(),
     };
 
-    let sites = args.sites.split(",").collect();
-    let repo = Arc::new(Repository::<MysqlConnection>::new(&cfg.db_dsn));
+    let options = client::ClientOptions::from_config(&cfg);
+    let repo = match Repository::<DbConnection>::from_config(&cfg) {
+        Ok(repo) => Arc::new(repo),
+        Err(e) => fail(format!("{:#}", e)),
+    };
+    let notifier =
+        Arc::new(notify::Notifier::from_config(cfg.notifications).unwrap_or_else(|e| fail(e)));
 
-    let sc = repo.get_sites(sites).unwrap();
+    let sites = args.sites.as_deref().unwrap().split(",").collect();
 
-    let client = Arc::new(client::HttpClient::new());
+    let sc = repo
+        .get_sites(sites)
+        .unwrap_or_else(|e| fail(format!("failed to load sites: {:#}", e)));
+
+    let client: fetcher::SharedFetcher = match (&args.record, &args.replay) {
+        (_, Some(dir)) => Arc::new(vcr::ReplayFetcher::new(dir)),
+        (Some(dir), _) => Arc::new(
+            client::HttpClient::with_options(options)
+                .with_recorder(vcr::Recorder::new(dir).unwrap()),
+        ),
+        _ => Arc::new(client::HttpClient::with_options(options)),
+    };
 
     let addr_fetcher = Arc::new(address_fetcher::AddressFetcher::new(client.clone()));
 
@@ -55,83 +247,447 @@ async fn main() {
         args.import_locations,
     ));
 
-    let mut path = std::path::PathBuf::new();
-    path.push(args.out_file.clone().as_str());
-    path = std::path::absolute(path).unwrap();
+    let target =
+        cmdutils::OutputTarget::new(args.out_file.as_deref().unwrap(), args.combined).unwrap();
+    let (tx, rx) = tokio::sync::mpsc::channel(16);
+    let writer = tokio::spawn(cmdutils::run_writer(rx, target, args.format));
 
     let mut handles = Vec::new();
 
     let report: Arc<dashmap::DashMap<String, usize>> = Arc::new(dashmap::DashMap::new());
+    // sites whose import was rolled back, with the error
+    let failed_imports: Arc<dashmap::DashMap<String, String>> = Default::default();
+    let alerts: Arc<std::sync::Mutex<Vec<health::HealthAlert>>> = Default::default();
 
     for site in sc {
-        if site.parser_type == "external" || site.parser_type == "custom" {
-            println!("skipping {}", site.site_name);
+        if !site_scraper::is_scrapable(&site.parser_type) {
+            info!(
+                site_name = site.site_name,
+                "skipping, parser type is not scraped"
+            );
             continue;
         }
-        println!("{} {} {}", site.site_name, site.base_url, site.parser_type);
+        let site_span = info_span!(
+            "site",
+            site_name = site.site_name,
+            base_url = site.base_url,
+            parser_type = site.parser_type
+        );
 
         let scraper = Arc::clone(&scraper);
 
-        let out_file = args.out_file.clone();
-        let p = path.clone();
+        let team = args.team.clone();
+        let tx = tx.clone();
         let report = Arc::clone(&report);
+        let failed_imports = Arc::clone(&failed_imports);
+        let alerts = Arc::clone(&alerts);
         let repo = repo.clone();
+        let notifier = Arc::clone(&notifier);
+        // replayed runs are not real runs and stay out of the history
+        let record_run = args.replay.is_none();
 
-        let h = tokio::spawn(async move {
-            match scraper.process_site(&site, dt).await {
-                Ok(games) => {
-                    let mut rep = report.entry(site.site_name.clone()).or_default();
-                    *rep = games.len();
-
-                    let wrt: Box<dyn std::io::Write> = match out_file.as_str() {
-                        "-" => Box::new(std::io::stdout()),
-                        _ => {
-                            let dir = p.parent().unwrap();
-                            let path = dir.join(
-                                site.site_name + "_" + p.file_name().unwrap().to_str().unwrap(),
-                            );
+        let task = async move {
+            let started_at = chrono::Utc::now().naive_utc();
+            let res = scraper.process_site(&site, dt).await;
+            if record_run {
+                let run = check_health(&repo, &site.site_name, started_at, &res, &alerts).await;
+                if let Err(e) = repo.blocking(move |r| r.insert_scrape_run(&run)).await {
+                    error!("failed to save scrape run: {:#}", e);
+                }
+            }
 
-                            let file = std::fs::File::create(path).unwrap();
-                            Box::new(file)
-                        }
+            match res {
+                Ok(parsed) => {
+                    let games = parsed.games;
+                    report.insert(site.site_name.clone(), games.len());
+                    METRICS
+                        .games_scraped
+                        .with_label_values(&[&site.site_name])
+                        .set(games.len() as i64);
+                    METRICS
+                        .parse_errors
+                        .with_label_values(&[&site.site_name])
+                        .set(parsed.errors.len() as i64);
+
+                    let out_games = match &team {
+                        Some(team) => cmdutils::filter_team(games.clone(), team),
+                        _ => games.clone(),
                     };
-                    cmdutils::write_output(&games, wrt).unwrap();
+                    if tx.send((site.site_name.clone(), out_games)).await.is_err() {
+                        error!("output writer stopped, games not written");
+                    }
 
-                    if args.import_events {
+                    if args.import_events || args.sync {
                         let events: Vec<models::InsertEvent> = games
                             .into_iter()
-                            .map(|g| models::InsertEvent {
-                                site: g.site_name,
-                                datetime: g.date,
-                                home_team: g.home_team,
-                                guest_team: g.away_team,
-                                location: Some(g.location),
-                                division: Some(g.division),
-                                location_id: Some(0),
-                                surface_id: 0,
+                            .map(|g| {
+                                let div = division::parse(
+                                    &g.division,
+                                    &[g.home_team.as_str(), g.away_team.as_str()],
+                                );
+                                models::InsertEvent {
+                                    site: g.site_name,
+                                    datetime: g.date,
+                                    home_team: g.home_team,
+                                    guest_team: g.away_team,
+                                    location: Some(g.location),
+                                    division: Some(g.division),
+                                    location_id: Some(0),
+                                    surface_id: 0,
+                                    age_group: div.age_group,
+                                    tier: div.tier,
+                                    gender: div.gender,
+                                    season_type: div.season_type,
+                                }
                             })
                             .collect();
-                        repo.import_games(events).unwrap();
+                        // a game the parser skipped would look cancelled
+                        let sync = args.sync && parsed.errors.is_empty();
+                        if args.sync && !sync {
+                            warn!(
+                                errors = parsed.errors.len(),
+                                "not syncing a scrape with parse errors, only adding games"
+                            );
+                        }
+                        let window = site_scraper::scraped_window(dt);
+                        let res = if sync {
+                            let (from, to) = window;
+                            repo.sync_games(
+                                &site.site_name,
+                                from,
+                                to,
+                                events,
+                                args.max_cancelled_share,
+                            )
+                            .await
+                        } else {
+                            let changes = schedule_diff::import_changes(
+                                &*repo,
+                                &site.site_name,
+                                window,
+                                &events,
+                            )
+                            .await;
+                            repo.import_games(events).await.map(|_| changes)
+                        }
+                        .map(|mut changes| {
+                            // a game the parser skipped is not cancelled
+                            if !parsed.errors.is_empty() {
+                                changes.retain(|c| c.kind != schedule_diff::ChangeKind::Cancelled);
+                            }
+                            changes
+                        });
+                        match res {
+                            Err(e) => {
+                                error!("import rolled back: {:#}", e);
+                                failed_imports.insert(site.site_name.clone(), format!("{:#}", e));
+                            }
+                            Ok(changes) if !changes.is_empty() && !notifier.is_empty() => {
+                                info!(changes = changes.len(), "notifying schedule changes");
+                                if let Err(e) = notifier.notify(&changes).await {
+                                    error!("{:#}", e);
+                                }
+                            }
+                            Ok(_) => {}
+                        }
                     }
                 }
-                Err(e) => println!("failed {} {}", site.site_name, e),
+                Err(e) => error!("failed: {:#}", e),
             };
-        });
+        };
+        let h = tokio::spawn(task.instrument(site_span));
         handles.push(h);
     }
 
     for h in handles {
         h.await.unwrap();
     }
+    drop(tx);
+    if let Err(e) = writer.await.unwrap() {
+        error!("failed to write output: {}", e);
+    }
 
-    eprintln!("\n\nSUMMARY");
-    eprintln!("{:-<60}", "");
-    eprintln!("{:<40} | {:>10}", "Site", "Games");
-    eprintln!("{:-<60}", "");
     for e in report.iter() {
-        eprintln!("{:<40} | {:>10}", e.key(), e.value());
+        info!(site_name = e.key(), games = e.value(), "summary");
+    }
+    for e in failed_imports.iter() {
+        error!(site_name = e.key(), error = e.value(), "import failed");
     }
     scraper.clone().client.summary().await;
 
     addr_fetcher.total_addresses();
+
+    let alerts = alerts.lock().unwrap().clone();
+    report_alerts(&alerts, &args.health_report, &args.alert_webhook).await;
+
+    if let Some(path) = &args.metrics_file
+        && let Err(e) = std::fs::write(path, METRICS.render())
+    {
+        error!("failed to write metrics to {}: {}", path.display(), e);
+    }
+    if !failed_imports.is_empty() {
+        std::process::exit(1);
+    }
+}
+
+// compares the run with the site's history and returns the run to save
+async fn check_health(
+    repo: &Repository<DbConnection>,
+    site: &str,
+    started_at: chrono::NaiveDateTime,
+    res: &anyhow::Result<site_scraper::Parsed>,
+    alerts: &std::sync::Mutex<Vec<health::HealthAlert>>,
+) -> models::NewScrapeRun {
+    let mut run = models::NewScrapeRun {
+        site: site.into(),
+        started_at,
+        finished_at: chrono::Utc::now().naive_utc(),
+        games_found: 0,
+        parse_errors: 0,
+        status: "ok".into(),
+        error: None,
+        alert: None,
+    };
+    let parsed = match res {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            run.status = "failed".into();
+            run.error = Some(format!("{:#}", e));
+            return run;
+        }
+    };
+    run.games_found = parsed.games.len() as i32;
+    run.parse_errors = parsed.errors.len() as i32;
+
+    let opts = health::HealthOptions::default();
+    let (site_name, limit) = (site.to_string(), opts.history);
+    let history = match repo
+        .blocking(move |r| r.recent_scrape_runs(&site_name, limit))
+        .await
+    {
+        Ok(h) => h,
+        Err(e) => {
+            warn!("failed to load scrape runs of {}: {:#}", site, e);
+            return run;
+        }
+    };
+    if let Some(alert) = health::check(
+        site,
+        parsed.games.len(),
+        parsed.errors.len(),
+        &history,
+        &opts,
+    ) {
+        warn!(reason = ?alert.reason, "{}", alert.message);
+        run.alert = Some(alert.message.clone());
+        alerts.lock().unwrap().push(alert);
+    }
+    run
+}
+
+async fn report_alerts(
+    alerts: &[health::HealthAlert],
+    report: &Option<PathBuf>,
+    webhook: &Option<String>,
+) {
+    if let Some(path) = report {
+        let res = std::fs::File::create(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|f| health::write_report(alerts, f));
+        if let Err(e) = res {
+            error!("failed to write {}: {}", path.display(), e);
+        }
+    }
+    if let Some(url) = webhook
+        && !alerts.is_empty()
+        && let Err(e) = health::send_webhook(url, alerts).await
+    {
+        error!("failed to send alerts: {}", e);
+    }
+}
+
+async fn preview(
+    url: String,
+    parser: String,
+    date: Option<String>,
+    format: cmdutils::OutputFormat,
+) {
+    let dt = parse_month(date);
+
+    let mut site = preview_site(url, parser);
+    if let Err(e) = site_admin::validate_new_site(&mut site) {
+        fail(e);
+    }
+    if !site_scraper::is_scrapable(&site.parser_type) {
+        fail(format!(
+            "parser type {} is not scraped by this tool",
+            site.parser_type
+        ));
+    }
+
+    let client = Arc::new(client::HttpClient::new());
+    let addr_fetcher = Arc::new(address_fetcher::AddressFetcher::new(client.clone()));
+    let scraper = site_scraper::Scraper::new(
+        client,
+        addr_fetcher,
+        Arc::new(repository::NoopRepository),
+        false,
+    );
+
+    match scraper
+        .process_site(&site_admin::unsaved_config(&site), dt)
+        .await
+    {
+        Ok(parsed) => print_games(parsed, format),
+        Err(e) => fail(format!("failed {}: {:#}", site.base_url, e)),
+    }
+}
+
+async fn offline(
+    files: Vec<PathBuf>,
+    url: Option<String>,
+    pages: Option<PathBuf>,
+    parser: String,
+    date: Option<String>,
+    format: cmdutils::OutputFormat,
+) {
+    let dt = parse_month(date);
+
+    if let Err(e) = site_admin::validate_parser_type(&parser) {
+        fail(e);
+    }
+    let base_url = match url {
+        Some(url) => site_admin::validate_base_url(&url).unwrap_or_else(|e| fail(e)),
+        _ => "".into(),
+    };
+    let site = site_admin::unsaved_config(&preview_site(base_url, parser));
+
+    let pages_dir = pages
+        .or_else(|| files.first().and_then(|f| f.parent()).map(PathBuf::from))
+        .unwrap_or_else(|| fail("either schedule files or --pages is needed"));
+    let pages = saved_pages::SavedPages::new(pages_dir);
+
+    let contents = if files.is_empty() {
+        if site.base_url.is_empty() {
+            fail("--url is needed to find the schedule page in --pages");
+        }
+        let schedule_url = site_scraper::schedule_url(&site, dt);
+        match pages.load(&schedule_url) {
+            Ok(Some(c)) => vec![c],
+            Ok(None) => fail(format!("{} not found", pages.path(&schedule_url).display())),
+            Err(e) => fail(e),
+        }
+    } else {
+        files
+            .iter()
+            .map(|f| {
+                std::fs::read_to_string(f)
+                    .unwrap_or_else(|e| fail(format!("{}: {}", f.display(), e)))
+            })
+            .collect()
+    };
+
+    // every page comes from disk, nothing is fetched
+    let client = Arc::new(fetcher::FixtureFetcher::from_saved_pages(pages));
+    let addr_fetcher = Arc::new(address_fetcher::AddressFetcher::new(client.clone()));
+    let scraper = site_scraper::Scraper::new(
+        client,
+        addr_fetcher,
+        Arc::new(repository::NoopRepository),
+        false,
+    );
+
+    let mut parsed = site_scraper::Parsed::default();
+    for c in contents {
+        match scraper.process_page(&site, c, dt).await {
+            Ok(p) => {
+                parsed.games.extend(p.games);
+                parsed.errors.extend(p.errors);
+            }
+            Err(e) => fail(format!("failed to parse: {:#}", e)),
+        }
+    }
+    print_games(parsed, format);
+}
+
+fn fixture(file: &Path, name: &str, parser: &str, date: Option<String>, out_dir: &Path) {
+    if let Err(e) = site_admin::validate_parser_type(parser) {
+        fail(e);
+    }
+    let month = parse_month(date);
+    let contents = std::fs::read_to_string(file)
+        .unwrap_or_else(|e| fail(format!("{}: {}", file.display(), e)));
+    let (source, body) = if file.extension().is_some_and(|e| e == "json") {
+        let rec: vcr::Recording = serde_json::from_str(&contents)
+            .unwrap_or_else(|e| fail(format!("{}: {}", file.display(), e)));
+        (rec.url, rec.body)
+    } else {
+        (file.display().to_string(), contents)
+    };
+
+    let trimmed = site_scraper::trim_page(&body);
+    match site_scraper::parse_page("fixture", parser, trimmed.clone(), month) {
+        Ok(parsed) if parsed.games.is_empty() => warn!("the page has no games"),
+        Ok(parsed) => info!(
+            games = parsed.games.len(),
+            errors = parsed.errors.len(),
+            "parsed"
+        ),
+        Err(e) => fail(format!("failed to parse: {:#}", e)),
+    }
+
+    // tests/parsers.rs parses the page for the month in the first line
+    let header = format!(
+        "<!-- month: {}, from {} -->\n",
+        month.format("%Y-%m"),
+        source.replace("--", "-")
+    );
+    let path = out_dir.join(parser).join(format!("{}.html", name));
+    if let Err(e) = std::fs::create_dir_all(path.parent().unwrap())
+        .and_then(|_| std::fs::write(&path, header + &trimmed))
+    {
+        fail(format!("{}: {}", path.display(), e));
+    }
+    println!(
+        "wrote {}, review it for personal data and run UPDATE_GOLDEN=1 cargo test --test parsers",
+        path.display()
+    );
+}
+
+// YYYY-MM, defaults to the current month
+fn parse_month(date: Option<String>) -> chrono::NaiveDate {
+    match date {
+        Some(ym) => chrono::NaiveDate::parse_from_str(&format!("{}-01", ym), "%Y-%m-%d")
+            .unwrap_or_else(|_| fail(format!("invalid month {}, expected YYYY-MM", ym))),
+        _ => chrono::prelude::Local::now().naive_local().date(),
+    }
+}
+
+fn preview_site(base_url: String, parser_type: String) -> models::NewSitesConfig {
+    models::NewSitesConfig {
+        site_name: "preview".into(),
+        display_name: None,
+        base_url,
+        home_team: None,
+        parser_type,
+        parser_config: None,
+        enabled: Some(true),
+        scrape_frequency_hours: None,
+        notes: None,
+    }
+}
+
+fn print_games(mut parsed: site_scraper::Parsed, format: cmdutils::OutputFormat) {
+    parsed.games.sort_by_key(|g| g.date);
+    cmdutils::write_output(&parsed.games, format, std::io::stdout().lock()).unwrap();
+    info!(
+        games = parsed.games.len(),
+        parse_errors = parsed.errors.len(),
+        "done"
+    );
+}
+
+fn fail(e: impl std::fmt::Display) -> ! {
+    error!("{}", e);
+    std::process::exit(1);
 }
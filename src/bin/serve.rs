@@ -0,0 +1,75 @@
+use calendar_scraper::Repository;
+use calendar_scraper::address_fetcher;
+use calendar_scraper::api;
+use calendar_scraper::client;
+use calendar_scraper::config;
+use calendar_scraper::db::DbConnection;
+use calendar_scraper::logging;
+use calendar_scraper::metrics::METRICS;
+use calendar_scraper::site_scraper;
+use clap::Parser;
+use std::sync::Arc;
+
+#[derive(Parser, Debug)]
+#[command(name = "serve")]
+struct Args {
+    #[arg(short, long, default_value = "127.0.0.1:8080")]
+    listen: String,
+    /// log as JSON lines on stderr
+    #[arg(long)]
+    log_json: bool,
+    #[command(flatten)]
+    config: config::ConfigArgs,
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+    logging::init(args.log_json);
+    let cfg = config::load(&args.config).unwrap_or_else(|e| fail(format!("{:#}", e)));
+
+    let repo = match Repository::<DbConnection>::from_config(&cfg) {
+        Ok(repo) => Arc::new(repo),
+        Err(e) => fail(format!("{:#}", e)),
+    };
+
+    // the scraper only runs dry runs for the admin api, so it never imports locations
+    let client = Arc::new(client::HttpClient::with_options(
+        client::ClientOptions::from_config(&cfg),
+    ));
+    let addr_fetcher = Arc::new(address_fetcher::AddressFetcher::new(client.clone()));
+    let scraper = Arc::new(site_scraper::Scraper::new(
+        client,
+        addr_fetcher,
+        repo.clone(),
+        false,
+    ));
+    let admin = api::admin::AdminState {
+        repo: repo.clone(),
+        scraper,
+        api_key: cfg.api_key.clone(),
+    };
+
+    let app = api::router(repo)
+        .merge(api::admin::router(admin))
+        .route("/metrics", axum::routing::get(metrics));
+
+    let listener = tokio::net::TcpListener::bind(&args.listen).await.unwrap();
+    tracing::info!("listening on {}", args.listen);
+    axum::serve(listener, app).await.unwrap();
+}
+
+async fn metrics() -> impl axum::response::IntoResponse {
+    (
+        [(
+            axum::http::header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        METRICS.render(),
+    )
+}
+
+fn fail(e: impl std::fmt::Display) -> ! {
+    tracing::error!("{}", e);
+    std::process::exit(1);
+}
@@ -0,0 +1,251 @@
+use calendar_scraper::Repository;
+use calendar_scraper::address_fetcher;
+use calendar_scraper::client;
+use calendar_scraper::config;
+use calendar_scraper::db::DbConnection;
+use calendar_scraper::logging;
+use calendar_scraper::models;
+use calendar_scraper::site_admin;
+use calendar_scraper::site_scraper;
+use clap::{Parser, Subcommand};
+use std::io::Write;
+use std::sync::Arc;
+
+#[derive(Parser, Debug)]
+#[command(name = "sites")]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+    /// log as JSON lines on stderr
+    #[arg(long, global = true)]
+    log_json: bool,
+    #[command(flatten)]
+    config: config::ConfigArgs,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// add a site after a dry-run scrape of the current month
+    Add {
+        name: String,
+        #[arg(long)]
+        base_url: String,
+        #[arg(long, default_value = "calendar")]
+        parser: String,
+        #[arg(long)]
+        display_name: Option<String>,
+        #[arg(long)]
+        home_team: Option<String>,
+        #[arg(long)]
+        frequency_hours: Option<i32>,
+        #[arg(long)]
+        notes: Option<String>,
+        /// add the site disabled
+        #[arg(long)]
+        disabled: bool,
+        /// save without asking for confirmation
+        #[arg(short, long)]
+        yes: bool,
+        /// save even if the dry run fails
+        #[arg(long)]
+        force: bool,
+    },
+    Update {
+        name: String,
+        #[arg(long)]
+        base_url: Option<String>,
+        #[arg(long)]
+        parser: Option<String>,
+        #[arg(long)]
+        display_name: Option<String>,
+        #[arg(long)]
+        home_team: Option<String>,
+        #[arg(long)]
+        frequency_hours: Option<i32>,
+        #[arg(long)]
+        notes: Option<String>,
+    },
+    Enable {
+        name: String,
+    },
+    Disable {
+        name: String,
+    },
+    List,
+    Show {
+        name: String,
+    },
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+    logging::init(args.log_json);
+    let cfg = config::load(&args.config).unwrap_or_else(|e| fail(format!("{:#}", e)));
+
+    let repo = match Repository::<DbConnection>::from_config(&cfg) {
+        Ok(repo) => Arc::new(repo),
+        Err(e) => fail(format!("{:#}", e)),
+    };
+
+    match args.command {
+        Command::Add {
+            name,
+            base_url,
+            parser,
+            display_name,
+            home_team,
+            frequency_hours,
+            notes,
+            disabled,
+            yes,
+            force,
+        } => {
+            let mut site = models::NewSitesConfig {
+                site_name: name,
+                display_name,
+                base_url,
+                home_team,
+                parser_type: parser,
+                parser_config: None,
+                enabled: Some(!disabled),
+                scrape_frequency_hours: frequency_hours,
+                notes,
+            };
+            if let Err(e) = site_admin::validate_new_site(&mut site) {
+                fail(e);
+            }
+            let site_name = site.site_name.clone();
+            if db(&repo, move |r| r.get_site(&site_name)).await.is_some() {
+                fail(format!("site {} already exists", site.site_name));
+            }
+
+            let client = Arc::new(client::HttpClient::with_options(
+                client::ClientOptions::from_config(&cfg),
+            ));
+            let addr_fetcher = Arc::new(address_fetcher::AddressFetcher::new(client.clone()));
+            let scraper = site_scraper::Scraper::new(client, addr_fetcher, repo.clone(), false);
+
+            match site_admin::dry_run(&scraper, &site_admin::unsaved_config(&site)).await {
+                Ok(games) => {
+                    println!("dry run found {} games", games.len());
+                    for g in games.iter().take(site_admin::PREVIEW_GAMES) {
+                        println!(
+                            "{} | {} @ {} | {} | {}",
+                            g.date, g.away_team, g.home_team, g.location, g.division
+                        );
+                    }
+                }
+                Err(e) if force => tracing::warn!("dry run failed: {:#}", e),
+                Err(e) => fail(format!(
+                    "dry run failed, use --force to save anyway: {:#}",
+                    e
+                )),
+            }
+
+            if !yes && !confirm(&format!("save site {}?", site.site_name)) {
+                println!("not saved");
+                return;
+            }
+            let site_name = site.site_name.clone();
+            db(&repo, move |r| r.insert_site(&site)).await;
+            println!("saved {}", site_name);
+        }
+        Command::Update {
+            name,
+            base_url,
+            parser,
+            display_name,
+            home_team,
+            frequency_hours,
+            notes,
+        } => {
+            let mut changes = models::SitesConfigChanges {
+                display_name,
+                base_url,
+                home_team,
+                parser_type: parser,
+                scrape_frequency_hours: frequency_hours,
+                notes,
+                ..Default::default()
+            };
+            if let Err(e) = site_admin::validate_changes(&mut changes) {
+                fail(e);
+            }
+            update(&repo, name, changes).await;
+        }
+        Command::Enable { name } => {
+            let changes = models::SitesConfigChanges {
+                enabled: Some(true),
+                ..Default::default()
+            };
+            update(&repo, name, changes).await;
+        }
+        Command::Disable { name } => {
+            let changes = models::SitesConfigChanges {
+                enabled: Some(false),
+                ..Default::default()
+            };
+            update(&repo, name, changes).await;
+        }
+        Command::List => {
+            println!(
+                "{:<30} | {:<8} | {:<12} | Base url",
+                "Site", "Enabled", "Parser"
+            );
+            println!("{:-<80}", "");
+            for s in db(&repo, |r| r.list_sites()).await {
+                println!(
+                    "{:<30} | {:<8} | {:<12} | {}",
+                    s.site_name,
+                    s.enabled.unwrap_or(false),
+                    s.parser_type,
+                    s.base_url
+                );
+            }
+        }
+        Command::Show { name } => {
+            let site_name = name.clone();
+            match db(&repo, move |r| r.get_site(&site_name)).await {
+                Some(s) => println!("{}", serde_json::to_string_pretty(&s).unwrap()),
+                None => fail(format!("site {} not found", name)),
+            }
+        }
+    }
+}
+
+async fn update(
+    repo: &Repository<DbConnection>,
+    name: String,
+    changes: models::SitesConfigChanges,
+) {
+    let site_name = name.clone();
+    if !db(repo, move |r| r.update_site(&site_name, &changes)).await {
+        fail(format!("site {} not found", name));
+    }
+    println!("updated {}", name);
+}
+
+// runs a repository call off the runtime, exits on database errors
+async fn db<T, F>(repo: &Repository<DbConnection>, f: F) -> T
+where
+    T: Send + 'static,
+    F: FnOnce(&Repository<DbConnection>) -> anyhow::Result<T> + Send + 'static,
+{
+    repo.blocking(f)
+        .await
+        .unwrap_or_else(|e| fail(format!("{:#}", e)))
+}
+
+fn confirm(question: &str) -> bool {
+    print!("{} [y/N] ", question);
+    std::io::stdout().flush().unwrap();
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).unwrap();
+    matches!(answer.trim(), "y" | "Y" | "yes")
+}
+
+fn fail(e: impl std::fmt::Display) -> ! {
+    tracing::error!("{}", e);
+    std::process::exit(1);
+}
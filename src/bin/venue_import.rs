@@ -1,16 +1,28 @@
 use calendar_scraper::config;
-use calendar_scraper::connect_db;
+use calendar_scraper::db::DbConnection;
+use calendar_scraper::logging;
+use diesel::Connection;
 
 use calendar_scraper::venue_import;
 
 fn main() {
-    let cfg = config::load();
+    logging::init(false);
+    let cfg = config::load(&config::ConfigArgs::default()).unwrap_or_else(|e| {
+        tracing::error!("{:#}", e);
+        std::process::exit(1);
+    });
 
-    let mut dbh = connect_db(&cfg);
+    let mut dbh = DbConnection::establish(&cfg.db_dsn).unwrap_or_else(|e| {
+        tracing::error!("failed to connect to the database: {}", e);
+        std::process::exit(1);
+    });
 
-    println!("{:?}", cfg);
+    tracing::debug!(?cfg, "starting");
 
     let f = venue_import::parse_file("m.json");
 
-    venue_import::import(&mut dbh, f);
+    if let Err(e) = venue_import::import(&mut dbh, f, cfg.import_chunk_size) {
+        tracing::error!("{:#}", e);
+        std::process::exit(1);
+    }
 }
@@ -1,3 +1,6 @@
+use crate::fetcher::Fetcher;
+use crate::metrics::METRICS;
+use crate::vcr::{Recorder, Recording, RequestKind};
 use anyhow::{Result, anyhow};
 use chrono::Duration;
 use dashmap::DashMap;
@@ -6,27 +9,65 @@ use reqwest::header;
 use std::error::Error;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time;
-use tokio::sync::{Mutex, RwLock, Semaphore};
+use tokio::sync::{RwLock, Semaphore};
+use tracing::{debug, info, warn};
 use url::Url;
 
 pub struct HttpClient {
     client: reqwest::Client,
     client_auto_redirect: reqwest::Client,
+    options: ClientOptions,
+    recorder: Option<Recorder>,
     sem_per_host: Arc<DashMap<String, Arc<Semaphore>>>,
     sem_global: Arc<Semaphore>,
-    total_requests_made: Mutex<u64>,
-    total_retry: Mutex<u64>,
-    total_failed: Mutex<u64>,
+    total_requests_made: AtomicU64,
+    total_retry: AtomicU64,
+    total_failed: AtomicU64,
 }
 
+#[derive(Debug, Clone)]
 pub enum Response {
     Content(String),
     Redirect(String),
 }
 
+#[derive(Debug, Clone)]
+pub struct ClientOptions {
+    pub timeout: time::Duration,
+    /// retries after the first attempt, for connection errors and 5xx responses
+    pub max_retries: u32,
+    pub retry_delay: time::Duration,
+    pub max_requests_per_host: usize,
+}
+
+impl Default for ClientOptions {
+    fn default() -> Self {
+        Self {
+            timeout: time::Duration::from_secs(120),
+            max_retries: 3,
+            retry_delay: time::Duration::from_secs(2),
+            max_requests_per_host: 10,
+        }
+    }
+}
+
+impl ClientOptions {
+    pub fn from_config(cfg: &crate::config::AppConfig) -> Self {
+        Self {
+            max_requests_per_host: cfg.max_requests_per_host,
+            ..Self::default()
+        }
+    }
+}
+
 impl HttpClient {
     pub fn new() -> Self {
+        Self::with_options(ClientOptions::default())
+    }
+
+    pub fn with_options(options: ClientOptions) -> Self {
         let mut headers = header::HeaderMap::new();
         headers.insert(
             header::USER_AGENT,
@@ -53,7 +94,7 @@ impl HttpClient {
         );
 
         let c = reqwest::Client::builder()
-            .timeout(time::Duration::from_secs(120))
+            .timeout(options.timeout)
             .connect_timeout(time::Duration::from_secs(10))
             .pool_max_idle_per_host(3)
             .pool_idle_timeout(time::Duration::from_secs(5))
@@ -63,7 +104,7 @@ impl HttpClient {
             .unwrap();
 
         let c_auto_redirect = reqwest::Client::builder()
-            .timeout(time::Duration::from_secs(120))
+            .timeout(options.timeout)
             .connect_timeout(time::Duration::from_secs(10))
             .pool_max_idle_per_host(3)
             .pool_idle_timeout(time::Duration::from_secs(5))
@@ -74,14 +115,55 @@ impl HttpClient {
         Self {
             client: c,
             client_auto_redirect: c_auto_redirect,
+            options,
+            recorder: None,
             sem_per_host: Arc::new(DashMap::new()),
             sem_global: Arc::new(Semaphore::new(80)),
-            total_failed: Mutex::new(0),
-            total_retry: Mutex::new(0),
-            total_requests_made: Mutex::new(0),
+            total_failed: AtomicU64::new(0),
+            total_retry: AtomicU64::new(0),
+            total_requests_made: AtomicU64::new(0),
         }
     }
 
+    /// Saves every response returned from now on, see `vcr`.
+    pub fn with_recorder(mut self, recorder: Recorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    fn record(&self, rec: Recording) {
+        if let Some(recorder) = &self.recorder
+            && let Err(e) = recorder.record(&rec)
+        {
+            warn!(url = %rec.url, error = %e, "failed to record response");
+        }
+    }
+
+    // failed attempts are recorded too, so a replay of a failed run fails the same way
+    fn record_error(&self, kind: RequestKind, url: &str, attempt: u32, error: &reqwest::Error) {
+        if self.recorder.is_some() {
+            let mut rec = Recording::failed(kind, url, error.to_string());
+            rec.attempt = attempt;
+            self.record(rec);
+        }
+    }
+
+    async fn record_server_error(
+        &self,
+        kind: RequestKind,
+        url: &str,
+        attempt: u32,
+        response: reqwest::Response,
+    ) {
+        if self.recorder.is_some() {
+            let mut rec = Recording::new(kind, url, &response);
+            rec.attempt = attempt;
+            rec.body = response.text().await.unwrap_or_default();
+            self.record(rec);
+        }
+    }
+
+    #[tracing::instrument(name = "request", skip(self), fields(attempt))]
     pub async fn get(&self, url: &str) -> Result<Response> {
         let u = http::uri::Uri::from_str(url)?;
         let host = u.host().unwrap().to_string();
@@ -89,51 +171,68 @@ impl HttpClient {
         // Get or create semaphore for this host (thread-safe)
         let sem = self
             .sem_per_host
-            .entry(host)
-            .or_insert_with(|| Arc::new(Semaphore::new(10)))
+            .entry(host.clone())
+            .or_insert_with(|| Arc::new(Semaphore::new(self.options.max_requests_per_host)))
             .clone();
 
         let mut t = 0;
 
         loop {
-            if t > 3 {
-                let mut n = self.total_failed.lock().await;
-                *n += 1;
+            if t > self.options.max_retries {
+                self.total_failed.fetch_add(1, Ordering::Relaxed);
+                METRICS.http_failures.with_label_values(&[&host]).inc();
                 return Err(anyhow!("retry failed"));
             }
             t += 1;
+            tracing::Span::current().record("attempt", t);
 
             let _global_permit = self.sem_global.acquire().await?;
             let _permit = sem.acquire().await?;
 
-            {
-                let mut n = self.total_requests_made.lock().await;
-                *n += 1;
-            }
+            self.total_requests_made.fetch_add(1, Ordering::Relaxed);
+            METRICS.http_requests.with_label_values(&[&host]).inc();
 
             if t > 1 {
-                let mut n = self.total_retry.lock().await;
-                *n += 1;
+                self.total_retry.fetch_add(1, Ordering::Relaxed);
+                METRICS.http_retries.with_label_values(&[&host]).inc();
             }
+            let started = time::Instant::now();
             let r = self.client.get(url).send().await;
+            METRICS.observe_response(
+                &host,
+                r.as_ref().ok().map(|r| r.status().as_u16()),
+                started.elapsed(),
+            );
 
             let response = match r {
                 Ok(s) => s,
                 Err(e) => {
                     drop(_global_permit);
-                    eprintln!("Request failed for URL: {}. retrying", url);
-                    eprintln!("Error: {}", e);
-                    eprintln!("Is timeout: {}", e.is_timeout());
-                    eprintln!("Is connect: {}", e.is_connect());
-                    eprintln!("Is request: {}", e.is_request());
-
-                    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+                    self.record_error(RequestKind::Get, url, t, &e);
+                    warn!(
+                        error = %e,
+                        source = ?Error::source(&e).map(|s| s.to_string()),
+                        timeout = e.is_timeout(),
+                        connect = e.is_connect(),
+                        "request failed, retrying"
+                    );
+
+                    tokio::time::sleep(self.options.retry_delay).await;
                     continue;
                 }
             };
             // drop(_permit);
             // drop(_global_permit);
 
+            if response.status().is_server_error() {
+                drop(_global_permit);
+                warn!(status = %response.status(), "server error, retrying");
+                self.record_server_error(RequestKind::Get, url, t, response)
+                    .await;
+                tokio::time::sleep(self.options.retry_delay).await;
+                continue;
+            }
+
             if let Some(redirect) = response.headers().get("location") {
                 let redirect: String = redirect.to_str()?.into();
                 let p = redirect
@@ -143,27 +242,44 @@ impl HttpClient {
                 let mut u = Url::parse(url)?;
                 u.set_path(p);
                 let final_url = u.to_string();
+                if self.recorder.is_some() {
+                    let mut rec = Recording::new(RequestKind::Get, url, &response);
+                    rec.attempt = t;
+                    rec.redirect = Some(final_url.clone());
+                    self.record(rec);
+                }
                 return Ok(Response::Redirect(final_url));
             }
+            let rec = self
+                .recorder
+                .as_ref()
+                .map(|_| Recording::new(RequestKind::Get, url, &response));
             let contents = match response.text().await {
                 Ok(c) => c,
                 Err(e) => {
-                    eprintln!("Failed to read response text for URL: {}", url);
-                    eprintln!("Error: {}", e);
-                    if let Some(source) = Error::source(&e) {
-                        eprintln!("Source: {}", source);
-                    }
+                    self.record_error(RequestKind::Get, url, t, &e);
+                    warn!(
+                        error = %e,
+                        source = ?Error::source(&e).map(|s| s.to_string()),
+                        "failed to read response, retrying"
+                    );
                     continue;
                 }
             };
             if t > 1 {
-                eprintln!("retry successful for read response");
+                debug!("retry successful");
+            }
+            if let Some(mut rec) = rec {
+                rec.attempt = t;
+                rec.body = contents.clone();
+                self.record(rec);
             }
 
             return Ok(Response::Content(contents));
         }
     }
 
+    #[tracing::instrument(name = "request", skip(self), fields(attempt))]
     pub async fn get_auto_redirect(&self, url: &str) -> Result<String> {
         let u = http::uri::Uri::from_str(url)?;
         let host = u.host().unwrap().to_string();
@@ -176,45 +292,62 @@ impl HttpClient {
         // Get or create semaphore for this host (thread-safe)
         let sem = self
             .sem_per_host
-            .entry(host)
-            .or_insert_with(|| Arc::new(Semaphore::new(10)))
+            .entry(host.clone())
+            .or_insert_with(|| Arc::new(Semaphore::new(self.options.max_requests_per_host)))
             .clone();
         let _permit = sem.acquire().await?;
 
         let mut t = 0;
         let response = loop {
-            if t > 3 {
-                let mut n = self.total_failed.lock().await;
-                *n += 1;
+            if t > self.options.max_retries {
+                self.total_failed.fetch_add(1, Ordering::Relaxed);
+                METRICS.http_failures.with_label_values(&[&host]).inc();
                 return Err(anyhow!("retry failed"));
             }
             t += 1;
+            tracing::Span::current().record("attempt", t);
             let _global_permit = self.sem_global.acquire().await?;
-            {
-                let mut n = self.total_requests_made.lock().await;
-                *n += 1;
-            }
+            self.total_requests_made.fetch_add(1, Ordering::Relaxed);
+            METRICS.http_requests.with_label_values(&[&host]).inc();
 
+            if t > 1 {
+                self.total_retry.fetch_add(1, Ordering::Relaxed);
+                METRICS.http_retries.with_label_values(&[&host]).inc();
+            }
+            let started = time::Instant::now();
             let r = self.client_auto_redirect.get(url).send().await;
+            METRICS.observe_response(
+                &host,
+                r.as_ref().ok().map(|r| r.status().as_u16()),
+                started.elapsed(),
+            );
 
             match r {
+                Ok(s) if s.status().is_server_error() => {
+                    drop(_global_permit);
+                    warn!(status = %s.status(), "server error, retrying");
+                    self.record_server_error(RequestKind::Auto, url, t, s).await;
+                    tokio::time::sleep(self.options.retry_delay).await;
+                    continue;
+                }
                 Ok(s) => {
                     if t > 1 {
-                        let mut n = self.total_requests_made.lock().await;
-                        *n += 1;
-                        eprintln!("retry successful");
+                        debug!("retry successful");
                     }
                     break s;
                 }
                 Err(e) => {
                     drop(_global_permit);
-                    eprintln!("Request failed for URL: {}. retrying", url);
-                    eprintln!("Error: {}", e);
-                    eprintln!("Is timeout: {}", e.is_timeout());
-                    eprintln!("Is connect: {}", e.is_connect());
-                    eprintln!("Is request: {}", e.is_request());
-
-                    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+                    self.record_error(RequestKind::Auto, url, t, &e);
+                    warn!(
+                        error = %e,
+                        source = ?Error::source(&e).map(|s| s.to_string()),
+                        timeout = e.is_timeout(),
+                        connect = e.is_connect(),
+                        "request failed, retrying"
+                    );
+
+                    tokio::time::sleep(self.options.retry_delay).await;
                     continue;
                 }
             }
@@ -222,34 +355,63 @@ impl HttpClient {
 
         drop(_permit);
 
+        let rec = self.recorder.as_ref().map(|_| {
+            let mut rec = Recording::new(RequestKind::Auto, url, &response);
+            rec.attempt = t;
+            rec
+        });
         let contents = response.text().await.map_err(|e| {
-            eprintln!("Failed to read response text for URL: {}", url);
-            eprintln!("Error: {}", e);
-            if let Some(source) = Error::source(&e) {
-                eprintln!("Source: {}", source);
-            }
+            self.record_error(RequestKind::Auto, url, t, &e);
+            warn!(
+                error = %e,
+                source = ?Error::source(&e).map(|s| s.to_string()),
+                "failed to read response"
+            );
             e
         });
 
         match contents {
-            Ok(c) => Ok(c),
+            Ok(c) => {
+                if let Some(mut rec) = rec {
+                    rec.body = c.clone();
+                    self.record(rec);
+                }
+                Ok(c)
+            }
             Err(e) => {
-                let mut n = self.total_failed.lock().await;
-                *n += 1;
+                self.total_failed.fetch_add(1, Ordering::Relaxed);
+                METRICS.http_failures.with_label_values(&[&host]).inc();
                 Err(anyhow!("{}", e))
             }
         }
     }
 
     pub async fn summary(&self) {
-        let total_requests = self.total_requests_made.lock().await;
-        let total_retry = self.total_retry.lock().await;
-        let total_failed = self.total_failed.lock().await;
-
-        eprintln!("{:-<60}", "");
-        eprintln!("{:<30}: {}", "Total Requests", *total_requests);
-        eprintln!("{:<30}: {}", "Total Retries", *total_retry);
-        eprintln!("{:<30}: {}", "Total Failed", *total_failed);
+        let total_requests = self.total_requests_made.load(Ordering::Relaxed);
+        let total_retry = self.total_retry.load(Ordering::Relaxed);
+        let total_failed = self.total_failed.load(Ordering::Relaxed);
+
+        info!(
+            requests = total_requests,
+            retries = total_retry,
+            failed = total_failed,
+            "http client summary"
+        );
+    }
+}
+
+#[async_trait::async_trait]
+impl Fetcher for HttpClient {
+    async fn get(&self, url: &str) -> Result<Response> {
+        HttpClient::get(self, url).await
+    }
+
+    async fn get_auto_redirect(&self, url: &str) -> Result<String> {
+        HttpClient::get_auto_redirect(self, url).await
+    }
+
+    async fn summary(&self) {
+        HttpClient::summary(self).await
     }
 }
 
@@ -298,14 +460,14 @@ impl HttpClientCached {
         let guard = s.read().await;
 
         if *guard != "" {
-            eprintln!("cache hit ");
+            debug!(url, "cache hit");
             return Ok((*guard).to_string());
         }
         drop(guard);
         let mut guard = s.write().await;
 
         if *guard != "" {
-            eprintln!("cache hit ");
+            debug!(url, "cache hit");
             return Ok((*guard).to_string());
         }
 
@@ -326,35 +488,47 @@ impl HttpClientCached {
         let _permit = sem.acquire().await?;
 
         let response = self.client.get(url).send().await.map_err(|e| {
-            eprintln!("Request failed for URL: {}", url);
-            eprintln!("Error: {}", e);
-            eprintln!("Is timeout: {}", e.is_timeout());
-            eprintln!("Is connect: {}", e.is_connect());
-            eprintln!("Is request: {}", e.is_request());
-            if let Some(status) = e.status() {
-                eprintln!("Status code: {}", status);
-            }
-            if let Some(source) = Error::source(&e) {
-                eprintln!("Source: {}", source);
-                let mut src = source;
-                while let Some(next) = Error::source(src) {
-                    eprintln!("  Caused by: {}", next);
-                    src = next;
-                }
+            let mut causes = Vec::new();
+            let mut src = Error::source(&e);
+            while let Some(next) = src {
+                causes.push(next.to_string());
+                src = Error::source(next);
             }
+            warn!(
+                url,
+                error = %e,
+                status = ?e.status(),
+                timeout = e.is_timeout(),
+                connect = e.is_connect(),
+                ?causes,
+                "request failed"
+            );
             e
         })?;
         drop(_global_permit);
         drop(_permit);
         let contents = response.text().await.map_err(|e| {
-            eprintln!("Failed to read response text for URL: {}", url);
-            eprintln!("Error: {}", e);
-            if let Some(source) = Error::source(&e) {
-                eprintln!("Source: {}", source);
-            }
+            warn!(
+                url,
+                error = %e,
+                source = ?Error::source(&e).map(|s| s.to_string()),
+                "failed to read response"
+            );
             e
         })?;
         *guard = contents.clone();
         Ok(contents)
     }
 }
+
+// the cached client always follows redirects, so both calls return the final page
+#[async_trait::async_trait]
+impl Fetcher for HttpClientCached {
+    async fn get(&self, url: &str) -> Result<Response> {
+        Ok(Response::Content(HttpClientCached::get(self, url).await?))
+    }
+
+    async fn get_auto_redirect(&self, url: &str) -> Result<String> {
+        HttpClientCached::get(self, url).await
+    }
+}
@@ -1,8 +1,63 @@
+use crate::ics;
 use crate::site_scraper;
-use anyhow::Result;
+use anyhow::{Context, Result};
 use csv;
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc;
+use tracing::warn;
 
-pub fn write_output(games: &Vec<site_scraper::ScrapedGame>, w: impl std::io::Write) -> Result<()> {
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum OutputFormat {
+    Csv,
+    Json,
+    Ndjson,
+    Ics,
+}
+
+// every field of a game, unlike the csv columns which leave out address_url
+#[derive(serde::Serialize)]
+struct JsonGame<'a> {
+    date: chrono::NaiveDateTime,
+    site_name: &'a str,
+    site_display_name: Option<&'a str>,
+    home_team: &'a str,
+    away_team: &'a str,
+    location: &'a str,
+    division: &'a str,
+    address_url: &'a str,
+    address: &'a str,
+}
+
+impl<'a> From<&'a site_scraper::ScrapedGame> for JsonGame<'a> {
+    fn from(g: &'a site_scraper::ScrapedGame) -> Self {
+        JsonGame {
+            date: g.date,
+            site_name: &g.site_name,
+            site_display_name: g.site_display_name.as_deref(),
+            home_team: &g.home_team,
+            away_team: &g.away_team,
+            location: &g.location,
+            division: &g.division,
+            address_url: &g.address_url,
+            address: &g.address,
+        }
+    }
+}
+
+pub fn write_output(
+    games: &[site_scraper::ScrapedGame],
+    format: OutputFormat,
+    w: impl std::io::Write,
+) -> Result<()> {
+    match format {
+        OutputFormat::Csv => write_csv(games, w),
+        OutputFormat::Json => write_json(games, w),
+        OutputFormat::Ndjson => write_ndjson(games, w),
+        OutputFormat::Ics => write_ics(games, w),
+    }
+}
+
+pub fn write_csv(games: &[site_scraper::ScrapedGame], w: impl std::io::Write) -> Result<()> {
     let mut wrt = csv::Writer::from_writer(w);
 
     for g in games.iter() {
@@ -10,3 +65,258 @@ pub fn write_output(games: &Vec<site_scraper::ScrapedGame>, w: impl std::io::Wri
     }
     Ok(())
 }
+
+/// Writes a json array one game at a time instead of building the whole document first.
+pub fn write_json(games: &[site_scraper::ScrapedGame], mut w: impl std::io::Write) -> Result<()> {
+    w.write_all(b"[")?;
+    for (i, g) in games.iter().enumerate() {
+        if i > 0 {
+            w.write_all(b",")?;
+        }
+        w.write_all(b"\n")?;
+        serde_json::to_writer(&mut w, &JsonGame::from(g))?;
+    }
+    w.write_all(b"\n]\n")?;
+    w.flush()?;
+    Ok(())
+}
+
+pub fn write_ndjson(games: &[site_scraper::ScrapedGame], mut w: impl std::io::Write) -> Result<()> {
+    for g in games.iter() {
+        serde_json::to_writer(&mut w, &JsonGame::from(g))?;
+        w.write_all(b"\n")?;
+    }
+    w.flush()?;
+    Ok(())
+}
+
+pub fn write_ics(games: &[site_scraper::ScrapedGame], w: impl std::io::Write) -> Result<()> {
+    let name = match games.first() {
+        Some(g) if games.iter().all(|o| o.site_name == g.site_name) => g
+            .site_display_name
+            .as_deref()
+            .unwrap_or(g.site_name.as_str()),
+        _ => "schedule",
+    };
+    let events: Vec<ics::CalendarEvent> = games.iter().map(ics::CalendarEvent::from).collect();
+    ics::write_calendar(name, &events, w)
+}
+
+/// Where scrape results are written: one file per site named `<site>_<file name>` next to
+/// the given path, or every site in a single file or on stdout.
+#[derive(Debug, Clone)]
+pub enum OutputTarget {
+    PerSite(PathBuf),
+    Combined(Option<PathBuf>),
+}
+
+impl OutputTarget {
+    /// `-` means stdout, which is always combined so sites do not interleave their rows.
+    pub fn new(out_file: &str, combined: bool) -> Result<Self> {
+        if out_file == "-" {
+            return Ok(OutputTarget::Combined(None));
+        }
+        let path = std::path::absolute(out_file)?;
+        if combined {
+            Ok(OutputTarget::Combined(Some(path)))
+        } else {
+            Ok(OutputTarget::PerSite(path))
+        }
+    }
+}
+
+/// Single writer for all site tasks. Per-site files are written as each site's games
+/// arrive, a site whose file fails is logged and skipped. Combined csv and ndjson are
+/// streamed too, one site after another with a single csv header. A combined json array
+/// or calendar is written once every site is done, sorted by site and date.
+pub async fn run_writer(
+    mut rx: mpsc::Receiver<(String, Vec<site_scraper::ScrapedGame>)>,
+    target: OutputTarget,
+    format: OutputFormat,
+) -> Result<()> {
+    let path = match target {
+        OutputTarget::PerSite(path) => {
+            while let Some((site_name, games)) = rx.recv().await {
+                let path = site_file_path(&path, &site_name);
+                let res = tokio::task::spawn_blocking(move || {
+                    let file = std::fs::File::create(&path)
+                        .with_context(|| format!("failed to create {}", path.display()))?;
+                    write_output(&games, format, std::io::BufWriter::new(file))
+                })
+                .await;
+                if let Err(e) = res.map_err(anyhow::Error::from).and_then(|r| r) {
+                    warn!("failed to write the games of {}: {:#}", site_name, e);
+                }
+            }
+            return Ok(());
+        }
+        OutputTarget::Combined(path) => path,
+    };
+
+    tokio::task::spawn_blocking(move || {
+        let w: Box<dyn std::io::Write> = match path {
+            Some(path) => Box::new(std::io::BufWriter::new(std::fs::File::create(path)?)),
+            _ => Box::new(std::io::stdout().lock()),
+        };
+        match format {
+            OutputFormat::Csv | OutputFormat::Ndjson => stream_output(rx, format, w),
+            OutputFormat::Json | OutputFormat::Ics => {
+                let mut combined = Vec::new();
+                while let Some((_, games)) = rx.blocking_recv() {
+                    combined.extend(games);
+                }
+                combined.sort_by(|a, b| (&a.site_name, a.date).cmp(&(&b.site_name, b.date)));
+                write_output(&combined, format, w)
+            }
+        }
+    })
+    .await?
+}
+
+// writes each site's games, by date, as soon as they arrive
+fn stream_output(
+    mut rx: mpsc::Receiver<(String, Vec<site_scraper::ScrapedGame>)>,
+    format: OutputFormat,
+    mut w: impl std::io::Write,
+) -> Result<()> {
+    if format == OutputFormat::Ndjson {
+        while let Some((_, mut games)) = rx.blocking_recv() {
+            games.sort_by_key(|g| g.date);
+            write_ndjson(&games, &mut w)?;
+        }
+        return Ok(());
+    }
+    // one csv writer for every site, it writes the header before the first row only
+    let mut wrt = csv::Writer::from_writer(w);
+    while let Some((_, mut games)) = rx.blocking_recv() {
+        games.sort_by_key(|g| g.date);
+        for g in games.iter() {
+            wrt.serialize(g)?;
+        }
+        wrt.flush()?;
+    }
+    Ok(())
+}
+
+fn site_file_path(path: &Path, site_name: &str) -> PathBuf {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{}_{}", site_name, file_name))
+}
+
+/// Keeps the games where `team` plays, matched case-insensitively on part of the name.
+pub fn filter_team(
+    games: Vec<site_scraper::ScrapedGame>,
+    team: &str,
+) -> Vec<site_scraper::ScrapedGame> {
+    let team = team.to_lowercase();
+    games
+        .into_iter()
+        .filter(|g| {
+            g.home_team.to_lowercase().contains(&team) || g.away_team.to_lowercase().contains(&team)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn game(home: &str) -> site_scraper::ScrapedGame {
+        site_scraper::ScrapedGame {
+            date: NaiveDate::from_ymd_opt(2025, 11, 8)
+                .unwrap()
+                .and_hms_opt(19, 30, 0)
+                .unwrap(),
+            site_name: "whitby".into(),
+            site_display_name: Some("Whitby Minor Hockey".into()),
+            home_team: home.into(),
+            away_team: "Ajax Knights".into(),
+            location: "Iroquois Park Arena".into(),
+            division: "U13 AA".into(),
+            address_url: "/Facilities/1234".into(),
+            address: "500 Victoria St W, Whitby, ON".into(),
+        }
+    }
+
+    #[test]
+    fn test_write_json_includes_every_field() {
+        let games = vec![game("Whitby Wildcats"), game("Oshawa Generals")];
+        let mut out = Vec::new();
+        write_json(&games, &mut out).unwrap();
+
+        let v: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(2, v.as_array().unwrap().len());
+        assert_eq!("/Facilities/1234", v[0]["address_url"]);
+        assert_eq!("Whitby Minor Hockey", v[0]["site_display_name"]);
+        assert_eq!("2025-11-08T19:30:00", v[1]["date"]);
+    }
+
+    #[test]
+    fn test_write_ndjson_one_game_per_line() {
+        let games = vec![game("Whitby Wildcats"), game("Oshawa Generals")];
+        let mut out = Vec::new();
+        write_ndjson(&games, &mut out).unwrap();
+
+        let lines: Vec<serde_json::Value> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(2, lines.len());
+        assert_eq!("Oshawa Generals", lines[1]["home_team"]);
+    }
+
+    #[tokio::test]
+    async fn test_run_writer_combined_csv_streams_sites_with_one_header() {
+        let path = std::env::temp_dir().join(format!(
+            "calendar-scraper-combined-{}.csv",
+            std::process::id()
+        ));
+        let target = OutputTarget::new(path.to_str().unwrap(), true).unwrap();
+        let (tx, rx) = mpsc::channel(4);
+        let writer = tokio::spawn(run_writer(rx, target, OutputFormat::Csv));
+
+        let mut ajax = game("Ajax Knights");
+        ajax.site_name = "ajax".into();
+        tx.send(("whitby".into(), vec![game("Whitby Wildcats")]))
+            .await
+            .unwrap();
+        tx.send(("ajax".into(), vec![ajax])).await.unwrap();
+        drop(tx);
+        writer.await.unwrap().unwrap();
+
+        let out = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(3, lines.len());
+        assert!(lines[0].starts_with("date,site_name,"));
+        // in the order the sites finished
+        assert!(lines[1].contains(",whitby,"));
+        assert!(lines[2].contains(",ajax,"));
+    }
+
+    #[tokio::test]
+    async fn test_run_writer_per_site_continues_after_a_failed_site() {
+        let dir =
+            std::env::temp_dir().join(format!("calendar-scraper-per-site-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let target = OutputTarget::new(dir.join("games.csv").to_str().unwrap(), false).unwrap();
+        let (tx, rx) = mpsc::channel(4);
+        let writer = tokio::spawn(run_writer(rx, target, OutputFormat::Csv));
+
+        // the file of this site would go in a directory that does not exist
+        tx.send(("no-such-dir/whitby".into(), vec![game("Whitby Wildcats")]))
+            .await
+            .unwrap();
+        tx.send(("ajax".into(), vec![game("Ajax Knights")]))
+            .await
+            .unwrap();
+        drop(tx);
+        writer.await.unwrap().unwrap();
+
+        let out = std::fs::read_to_string(dir.join("ajax_games.csv")).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(out.contains("Ajax Knights"));
+    }
+}
@@ -0,0 +1,138 @@
+// The database backend is picked at build time with exactly one of the `mysql` (the
+// default), `postgres` or `sqlite` cargo features, e.g.
+//
+//   cargo build --no-default-features --features sqlite
+//
+// and DB_DSN then names a database of that kind: a mysql:// or postgres:// url, or the
+// path of a SQLite file. Code that talks to the database uses the aliases here instead of
+// a concrete connection type.
+//
+// Each backend has its own migrations under migrations/, embedded in the binaries and
+// applied with `scrape migrate`.
+
+use anyhow::{Result, anyhow};
+use diesel_migrations::{EmbeddedMigrations, MigrationHarness, embed_migrations};
+
+#[cfg(not(any(feature = "mysql", feature = "postgres", feature = "sqlite")))]
+compile_error!("enable one of the features mysql, postgres or sqlite");
+
+#[cfg(any(
+    all(feature = "mysql", feature = "postgres"),
+    all(feature = "mysql", feature = "sqlite"),
+    all(feature = "postgres", feature = "sqlite")
+))]
+compile_error!(
+    "the features mysql, postgres and sqlite are exclusive, use --no-default-features to pick postgres or sqlite"
+);
+
+#[cfg(feature = "mysql")]
+pub type Backend = diesel::mysql::Mysql;
+#[cfg(feature = "mysql")]
+pub type DbConnection = diesel::MysqlConnection;
+/// Rust type of the BIGINT UNSIGNED columns, which only MySQL has; the other
+/// backends store them as BIGINT.
+#[cfg(feature = "mysql")]
+pub type UnsignedBig = u64;
+
+#[cfg(feature = "postgres")]
+pub type Backend = diesel::pg::Pg;
+#[cfg(feature = "postgres")]
+pub type DbConnection = diesel::PgConnection;
+#[cfg(feature = "postgres")]
+pub type UnsignedBig = i64;
+
+#[cfg(feature = "sqlite")]
+pub type Backend = diesel::sqlite::Sqlite;
+#[cfg(feature = "sqlite")]
+pub type DbConnection = diesel::SqliteConnection;
+#[cfg(feature = "sqlite")]
+pub type UnsignedBig = i64;
+
+/// Sets up each pooled SQLite connection: the scraper imports from several tasks at
+/// once, so writers wait for the lock instead of failing with "database is locked".
+#[cfg(feature = "sqlite")]
+#[derive(Debug, Clone, Copy)]
+pub struct SqliteSetup;
+
+#[cfg(feature = "sqlite")]
+impl diesel::r2d2::CustomizeConnection<DbConnection, diesel::r2d2::Error> for SqliteSetup {
+    fn on_acquire(&self, conn: &mut DbConnection) -> Result<(), diesel::r2d2::Error> {
+        use diesel::connection::SimpleConnection;
+
+        conn.batch_execute(
+            "PRAGMA busy_timeout = 5000; PRAGMA journal_mode = WAL; PRAGMA foreign_keys = ON;",
+        )
+        .map_err(diesel::r2d2::Error::QueryError)
+    }
+}
+
+/// Inserts `rows` into `table`, skipping rows that already exist: INSERT IGNORE on
+/// MySQL, ON CONFLICT DO NOTHING elsewhere. Diesel can't batch such inserts on SQLite, so
+/// there every row is its own statement, in one transaction.
+macro_rules! insert_ignore {
+    ($table:expr, $rows:expr, $conn:expr) => {{
+        #[cfg(not(feature = "sqlite"))]
+        let res = diesel::insert_into($table)
+            .values($rows)
+            .on_conflict_do_nothing()
+            .execute($conn);
+        #[cfg(feature = "sqlite")]
+        let res = diesel::Connection::transaction($conn, |conn| {
+            let mut n = 0;
+            for row in $rows {
+                n += diesel::insert_into($table)
+                    .values(row)
+                    .on_conflict_do_nothing()
+                    .execute(conn)?;
+            }
+            diesel::QueryResult::Ok(n)
+        });
+        res
+    }};
+}
+pub(crate) use insert_ignore;
+
+#[cfg(feature = "mysql")]
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations/mysql");
+#[cfg(feature = "postgres")]
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations/postgres");
+#[cfg(feature = "sqlite")]
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations/sqlite");
+
+/// Names of the migrations not yet applied to the database, oldest first, e.g.
+/// "2026-10-19-140000_create_scrape_runs".
+pub fn pending_migrations(conn: &mut DbConnection) -> Result<Vec<String>> {
+    let pending = conn
+        .pending_migrations(MIGRATIONS)
+        .map_err(|e| anyhow!("failed to read the applied migrations: {}", e))?;
+    Ok(pending.iter().map(|m| m.name().to_string()).collect())
+}
+
+/// Applies the pending migrations and returns their names.
+pub fn run_migrations(conn: &mut DbConnection) -> Result<Vec<String>> {
+    let pending = pending_migrations(conn)?;
+    conn.run_pending_migrations(MIGRATIONS)
+        .map_err(|e| anyhow!("migration failed: {}", e))?;
+    Ok(pending)
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod test {
+    use super::*;
+    use diesel::Connection;
+
+    #[test]
+    fn test_run_migrations() {
+        let mut conn = DbConnection::establish(":memory:").unwrap();
+        assert_eq!(
+            vec![
+                "2025-01-01-000000_initial_schema",
+                "2026-10-19-150000_add_deleted_at_to_events"
+            ],
+            pending_migrations(&mut conn).unwrap()
+        );
+        assert_eq!(2, run_migrations(&mut conn).unwrap().len());
+        assert!(pending_migrations(&mut conn).unwrap().is_empty());
+        assert!(run_migrations(&mut conn).unwrap().is_empty());
+    }
+}
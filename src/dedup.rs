@@ -0,0 +1,319 @@
+// Finds the same physical game imported from different sites, e.g. one organization lists
+// it as a home game ("vs") and the other as an away game ("@"). Events match when they are
+// on different sites, start within a time window, are at the same venue and have the same
+// normalized team pair. Each group is linked to one canonical event whose fields win.
+
+use crate::db::UnsignedBig;
+use crate::division;
+use crate::models::Event;
+use chrono::Duration;
+use std::collections::HashSet;
+
+pub struct DedupOptions {
+    pub window: Duration,
+    /// sites in order of preference when choosing the canonical event
+    pub site_priority: Vec<String>,
+}
+
+impl Default for DedupOptions {
+    fn default() -> Self {
+        Self {
+            window: Duration::minutes(15),
+            site_priority: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct DuplicateGroup {
+    pub canonical_id: UnsignedBig,
+    pub duplicate_ids: Vec<UnsignedBig>,
+    /// fields the canonical event is missing and takes from its duplicates
+    pub merged: MergedFields,
+}
+
+#[derive(Debug, Default, PartialEq, diesel::AsChangeset)]
+#[diesel(table_name=crate::schema::events)]
+pub struct MergedFields {
+    pub location_id: Option<i32>,
+    pub surface_id: Option<i32>,
+    pub division: Option<String>,
+    pub age_group: Option<String>,
+    pub tier: Option<String>,
+    pub gender: Option<String>,
+    pub season_type: Option<String>,
+}
+
+impl MergedFields {
+    pub fn is_empty(&self) -> bool {
+        *self == MergedFields::default()
+    }
+}
+
+pub fn find_duplicates(events: &[Event], opts: &DedupOptions) -> Vec<DuplicateGroup> {
+    let mut order: Vec<&Event> = events.iter().collect();
+    order.sort_by_key(|e| e.datetime);
+
+    let keys: Vec<(String, String)> = order.iter().map(|e| team_pair(e)).collect();
+    let mut grouped = vec![false; order.len()];
+    let mut groups = Vec::new();
+
+    for i in 0..order.len() {
+        if grouped[i] {
+            continue;
+        }
+        let mut members = vec![i];
+        let mut sites: HashSet<&str> = HashSet::from([order[i].site.as_str()]);
+
+        for j in i + 1..order.len() {
+            if order[j].datetime - order[i].datetime > opts.window {
+                break;
+            }
+            if grouped[j] || sites.contains(order[j].site.as_str()) {
+                continue;
+            }
+            if keys[i] == keys[j] && same_venue(order[i], order[j]) {
+                members.push(j);
+                sites.insert(order[j].site.as_str());
+            }
+        }
+
+        if members.len() < 2 {
+            continue;
+        }
+        for m in members.iter() {
+            grouped[*m] = true;
+        }
+        let members: Vec<&Event> = members.into_iter().map(|m| order[m]).collect();
+        groups.push(build_group(&members, opts));
+    }
+
+    groups
+}
+
+/// Lowercases team names and drops age group, tier and gender tokens, which sites
+/// include or leave out of team names inconsistently.
+pub fn normalize_team(name: &str) -> String {
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .filter(|t| division::parse_label(t) == division::DivisionInfo::default())
+        .map(|t| t.to_lowercase())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Lowercases a location and collapses its punctuation. Unlike `normalize_team` every
+/// token is kept: in "Rink A" or "Pad 1" the letter or number picks the ice surface.
+pub fn normalize_location(name: &str) -> String {
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(|t| t.to_lowercase())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+// the pair is unordered in case a site got home and away the wrong way round
+fn team_pair(e: &Event) -> (String, String) {
+    let home = normalize_team(&e.home_team);
+    let guest = normalize_team(&e.guest_team);
+    if home <= guest {
+        (home, guest)
+    } else {
+        (guest, home)
+    }
+}
+
+// location_id 0 means the venue has not been mapped yet, so fall back to the location text
+fn same_venue(a: &Event, b: &Event) -> bool {
+    match (a.location_id.unwrap_or(0), b.location_id.unwrap_or(0)) {
+        (0, _) | (_, 0) => {
+            let loc_a = a.location.as_deref().map(normalize_location);
+            let loc_b = b.location.as_deref().map(normalize_location);
+            loc_a.is_some() && loc_a == loc_b
+        }
+        (id_a, id_b) => id_a == id_b,
+    }
+}
+
+fn build_group(members: &[&Event], opts: &DedupOptions) -> DuplicateGroup {
+    let rank = |e: &Event| {
+        let priority = opts
+            .site_priority
+            .iter()
+            .position(|s| *s == e.site)
+            .unwrap_or(usize::MAX);
+        // prefer sites listed in the priority, then events with a mapped venue, then the oldest
+        (priority, e.location_id.unwrap_or(0) == 0, e.id)
+    };
+
+    let canonical = *members.iter().min_by_key(|e| rank(e)).unwrap();
+    let mut others: Vec<&Event> = members
+        .iter()
+        .filter(|e| e.id != canonical.id)
+        .copied()
+        .collect();
+    others.sort_by_key(|e| rank(e));
+
+    let mut merged = MergedFields::default();
+    for e in others.iter() {
+        if canonical.location_id.unwrap_or(0) == 0 && merged.location_id.is_none() {
+            merged.location_id = e.location_id.filter(|id| *id != 0);
+        }
+        if canonical.surface_id == 0 && merged.surface_id.is_none() && e.surface_id != 0 {
+            merged.surface_id = Some(e.surface_id);
+        }
+        fill(&mut merged.division, &canonical.division, &e.division);
+        fill(&mut merged.age_group, &canonical.age_group, &e.age_group);
+        fill(&mut merged.tier, &canonical.tier, &e.tier);
+        fill(&mut merged.gender, &canonical.gender, &e.gender);
+        fill(
+            &mut merged.season_type,
+            &canonical.season_type,
+            &e.season_type,
+        );
+    }
+
+    DuplicateGroup {
+        canonical_id: canonical.id,
+        duplicate_ids: others.iter().map(|e| e.id).collect(),
+        merged,
+    }
+}
+
+fn fill(merged: &mut Option<String>, canonical: &Option<String>, other: &Option<String>) {
+    if canonical.is_none() && merged.is_none() {
+        *merged = other.clone();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn event(
+        id: UnsignedBig,
+        site: &str,
+        hh: u32,
+        mm: u32,
+        home: &str,
+        guest: &str,
+        loc: i32,
+    ) -> Event {
+        Event {
+            id,
+            site: site.into(),
+            source_type: None,
+            datetime: NaiveDate::from_ymd_opt(2025, 11, 8)
+                .unwrap()
+                .and_hms_opt(hh, mm, 0)
+                .unwrap(),
+            home_team: home.into(),
+            oid_home: None,
+            guest_team: guest.into(),
+            oid_guest: None,
+            location: Some("Iroquois Park Arena".into()),
+            division: None,
+            location_id: Some(loc),
+            surface_id: 0,
+            date_created: NaiveDate::from_ymd_opt(2025, 11, 1)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap(),
+            age_group: None,
+            tier: None,
+            gender: None,
+            season_type: None,
+            canonical_id: None,
+            deleted_at: None,
+        }
+    }
+
+    #[test]
+    fn test_find_duplicates_across_sites() {
+        let mut ajax = event(
+            1,
+            "ajax",
+            19,
+            0,
+            "Whitby Wildcats",
+            "Ajax Knights U13 AA",
+            0,
+        );
+        ajax.division = Some("U13 AA".into());
+        let events = vec![
+            ajax,
+            event(
+                2,
+                "whitby",
+                19,
+                5,
+                "Whitby Wildcats U13 AA",
+                "Ajax Knights",
+                42,
+            ),
+            // same teams but a different site listing it hours later
+            event(3, "oshawa", 21, 0, "Whitby Wildcats", "Ajax Knights", 42),
+        ];
+
+        let groups = find_duplicates(&events, &DedupOptions::default());
+        assert_eq!(
+            vec![DuplicateGroup {
+                canonical_id: 2,
+                duplicate_ids: vec![1],
+                merged: MergedFields {
+                    division: Some("U13 AA".into()),
+                    ..Default::default()
+                },
+            }],
+            groups
+        );
+    }
+
+    #[test]
+    fn test_find_duplicates_site_priority() {
+        let events = vec![
+            event(1, "ajax", 19, 0, "Whitby Wildcats", "Ajax Knights", 0),
+            event(2, "whitby", 19, 0, "Ajax Knights", "Whitby Wildcats", 42),
+        ];
+        let opts = DedupOptions {
+            site_priority: vec!["ajax".into()],
+            ..Default::default()
+        };
+
+        let groups = find_duplicates(&events, &opts);
+        assert_eq!(1, groups[0].canonical_id);
+        assert_eq!(vec![2], groups[0].duplicate_ids);
+        assert_eq!(Some(42), groups[0].merged.location_id);
+    }
+
+    #[test]
+    fn test_same_site_or_venue_mismatch_not_duplicates() {
+        let events = vec![
+            event(1, "ajax", 19, 0, "Whitby Wildcats", "Ajax Knights", 7),
+            event(2, "ajax", 19, 0, "Whitby Wildcats", "Ajax Knights", 7),
+            event(3, "whitby", 19, 0, "Whitby Wildcats", "Ajax Knights", 8),
+        ];
+        assert!(find_duplicates(&events, &DedupOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn test_unmapped_venues_compare_by_rink() {
+        let at_rink = |id, site, rink: &str| {
+            let mut e = event(id, site, 19, 0, "Whitby Wildcats", "Ajax Knights", 0);
+            e.location = Some(rink.into());
+            e
+        };
+        let events = vec![
+            at_rink(1, "ajax", "Iroquois Park - Rink A"),
+            at_rink(2, "whitby", "iroquois park rink a"),
+        ];
+        assert_eq!(1, find_duplicates(&events, &DedupOptions::default()).len());
+
+        let events = vec![
+            at_rink(1, "ajax", "Iroquois Park - Rink A"),
+            at_rink(2, "whitby", "Iroquois Park - Rink B"),
+        ];
+        assert!(find_duplicates(&events, &DedupOptions::default()).is_empty());
+    }
+}
@@ -0,0 +1,204 @@
+// Parses free-text division labels such as "U13 AA Girls" or "Minor Peewee A - Playoffs"
+// into structured fields. Sites label divisions differently and the calendar parser falls
+// back to the subject owner (a team name) when there is no group element, so team names
+// are parsed too and used to fill whatever the division text did not provide.
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct DivisionInfo {
+    /// normalized as "U<age>", e.g. "U11"
+    pub age_group: Option<String>,
+    /// e.g. "AAA", "AA", "A", "BB", "HL"
+    pub tier: Option<String>,
+    /// "female" or "male"
+    pub gender: Option<String>,
+    /// "regular", "playoff", "exhibition", "preseason" or "tournament"
+    pub season_type: Option<String>,
+}
+
+const TIERS: [&str; 11] = [
+    "AAA", "AA", "AE", "A", "BB", "B", "CC", "C", "MD", "HL", "LL",
+];
+
+// Hockey Canada names for the age groups
+const NAMED_AGE_GROUPS: [(&str, &str); 7] = [
+    ("TYKE", "U7"),
+    ("NOVICE", "U9"),
+    ("ATOM", "U11"),
+    ("PEEWEE", "U13"),
+    ("BANTAM", "U15"),
+    ("MIDGET", "U18"),
+    ("JUVENILE", "U21"),
+];
+
+/// Parses `division` and fills any missing fields from `team_names`, in order.
+pub fn parse(division: &str, team_names: &[&str]) -> DivisionInfo {
+    let mut info = parse_label(division);
+
+    for name in team_names {
+        if info.is_complete() {
+            break;
+        }
+        let other = parse_label(name);
+        info.age_group = info.age_group.or(other.age_group);
+        info.tier = info.tier.or(other.tier);
+        info.gender = info.gender.or(other.gender);
+        info.season_type = info.season_type.or(other.season_type);
+    }
+    info
+}
+
+/// Parses a single label without any fallback.
+pub fn parse_label(label: &str) -> DivisionInfo {
+    let tokens = tokenize(label);
+    let mut info = DivisionInfo::default();
+
+    let mut i = 0;
+    while i < tokens.len() {
+        let tok = tokens[i].as_str();
+        let next = tokens.get(i + 1).map(|s| s.as_str());
+
+        if info.age_group.is_none()
+            && let Some(age) = parse_age(tok, next)
+        {
+            info.age_group = Some(age.0);
+            i += age.1;
+            continue;
+        }
+
+        if info.tier.is_none() {
+            if tok == "HOUSE" && next == Some("LEAGUE") {
+                info.tier = Some("HL".into());
+                i += 2;
+                continue;
+            }
+            if let Some(t) = TIERS.iter().find(|t| **t == tok) {
+                info.tier = Some(t.to_string());
+            }
+        }
+
+        if info.gender.is_none() {
+            info.gender = match tok {
+                "GIRLS" | "GIRL" | "FEMALE" | "WOMEN" | "WOMENS" | "LADIES" => {
+                    Some("female".into())
+                }
+                "BOYS" | "BOY" | "MALE" | "MEN" | "MENS" => Some("male".into()),
+                _ => None,
+            };
+        }
+
+        if info.season_type.is_none() {
+            info.season_type = match (tok, next) {
+                ("PLAYOFF" | "PLAYOFFS", _) => Some("playoff".into()),
+                ("EXHIBITION", _) => Some("exhibition".into()),
+                ("PRESEASON", _) | ("PRE", Some("SEASON")) => Some("preseason".into()),
+                ("TOURNAMENT", _) => Some("tournament".into()),
+                ("REGULAR", Some("SEASON")) => Some("regular".into()),
+                _ => None,
+            };
+        }
+        i += 1;
+    }
+
+    info
+}
+
+impl DivisionInfo {
+    fn is_complete(&self) -> bool {
+        self.age_group.is_some()
+            && self.tier.is_some()
+            && self.gender.is_some()
+            && self.season_type.is_some()
+    }
+}
+
+fn tokenize(label: &str) -> Vec<String> {
+    label
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(|t| t.to_ascii_uppercase())
+        .collect()
+}
+
+// returns the normalized age group and the number of tokens it consumed
+fn parse_age(tok: &str, next: Option<&str>) -> Option<(String, usize)> {
+    let is_age = |s: &str| !s.is_empty() && s.len() <= 2 && s.chars().all(|c| c.is_ascii_digit());
+
+    // U11
+    if let Some(n) = tok.strip_prefix('U')
+        && is_age(n)
+    {
+        return Some((format!("U{}", n), 1));
+    }
+    // 11U
+    if let Some(n) = tok.strip_suffix('U')
+        && is_age(n)
+    {
+        return Some((format!("U{}", n), 1));
+    }
+    // U-11, Under 11
+    if (tok == "U" || tok == "UNDER")
+        && let Some(n) = next.filter(|n| is_age(n))
+    {
+        return Some((format!("U{}", n), 2));
+    }
+    // Minor Atom, Major Bantam, Peewee
+    NAMED_AGE_GROUPS
+        .iter()
+        .find(|(name, _)| *name == tok)
+        .map(|(_, age)| (age.to_string(), 1))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn info(age: &str, tier: &str, gender: Option<&str>, season: Option<&str>) -> DivisionInfo {
+        DivisionInfo {
+            age_group: Some(age.into()),
+            tier: Some(tier.into()),
+            gender: gender.map(Into::into),
+            season_type: season.map(Into::into),
+        }
+    }
+
+    #[test]
+    fn test_parse_label() {
+        assert_eq!(
+            info("U13", "AA", Some("female"), None),
+            parse_label("U13 AA Girls")
+        );
+        assert_eq!(
+            info("U11", "A", None, Some("playoff")),
+            parse_label("U-11 A - Playoffs")
+        );
+        assert_eq!(
+            info("U15", "BB", None, None),
+            parse_label("Minor Bantam BB")
+        );
+        assert_eq!(
+            info("U9", "HL", None, None),
+            parse_label("Under 9 House League")
+        );
+        assert_eq!(
+            info("U18", "AE", Some("male"), None),
+            parse_label("18U AE Boys")
+        );
+    }
+
+    #[test]
+    fn test_parse_falls_back_to_team_names() {
+        let parsed = parse(
+            "Exhibition",
+            &["Whitby Wildcats U13 AA", "Ajax Knights U13 A"],
+        );
+        assert_eq!(info("U13", "AA", None, Some("exhibition")), parsed);
+    }
+
+    #[test]
+    fn test_parse_unknown() {
+        assert_eq!(
+            DivisionInfo::default(),
+            parse("Whitby Wildcats", &["Ajax Knights"])
+        );
+    }
+}
@@ -0,0 +1,121 @@
+// The HTTP side of scraping as a trait, so the scraper and address fetcher can run against
+// canned responses. `HttpClient` and `HttpClientCached` fetch over HTTP, `FixtureFetcher`
+// answers from pages given up front or saved to disk.
+
+use crate::client::Response;
+use crate::saved_pages::SavedPages;
+use anyhow::{Result, anyhow};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+const MAX_REDIRECTS: usize = 10;
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait::async_trait]
+pub trait Fetcher {
+    /// GET without following redirects.
+    async fn get(&self, url: &str) -> Result<Response>;
+
+    /// GET following redirects, returns the body of the final page.
+    async fn get_auto_redirect(&self, url: &str) -> Result<String>;
+
+    /// Prints request counts to stderr, for fetchers that keep them.
+    async fn summary(&self) {}
+}
+
+pub type SharedFetcher = std::sync::Arc<dyn Fetcher + Send + Sync>;
+
+/// Answers from pages added with `with_page` and `with_redirect`, then from saved pages.
+/// Unknown urls are an error, nothing is fetched.
+#[derive(Default)]
+pub struct FixtureFetcher {
+    pages: HashMap<String, Response>,
+    saved_pages: Option<SavedPages>,
+    requests: Mutex<Vec<String>>,
+}
+
+impl FixtureFetcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_saved_pages(pages: SavedPages) -> Self {
+        FixtureFetcher {
+            saved_pages: Some(pages),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_page(mut self, url: &str, contents: &str) -> Self {
+        self.pages
+            .insert(url.into(), Response::Content(contents.into()));
+        self
+    }
+
+    pub fn with_redirect(mut self, url: &str, location: &str) -> Self {
+        self.pages
+            .insert(url.into(), Response::Redirect(location.into()));
+        self
+    }
+
+    /// Urls requested so far, in order.
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+#[async_trait::async_trait]
+impl Fetcher for FixtureFetcher {
+    async fn get(&self, url: &str) -> Result<Response> {
+        self.requests.lock().unwrap().push(url.into());
+
+        if let Some(res) = self.pages.get(url) {
+            return Ok(res.clone());
+        }
+        if let Some(pages) = &self.saved_pages
+            && let Some(contents) = pages.load(url)?
+        {
+            return Ok(Response::Content(contents));
+        }
+        Err(anyhow!("no fixture for {}", url))
+    }
+
+    async fn get_auto_redirect(&self, url: &str) -> Result<String> {
+        let mut url = url.to_string();
+        for _ in 0..MAX_REDIRECTS {
+            match self.get(&url).await? {
+                Response::Content(contents) => return Ok(contents),
+                Response::Redirect(location) => url = location,
+            }
+        }
+        Err(anyhow!("too many redirects for {}", url))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_fixture_fetcher_follows_redirects() {
+        let f = FixtureFetcher::new()
+            .with_redirect(
+                "https://example.com/Venue/Moved/",
+                "https://example.com/Venue/1/",
+            )
+            .with_page("https://example.com/Venue/1/", "venue");
+
+        assert_eq!(
+            "venue",
+            f.get_auto_redirect("https://example.com/Venue/Moved/")
+                .await
+                .unwrap()
+        );
+        assert!(matches!(
+            f.get("https://example.com/Venue/Moved/").await.unwrap(),
+            Response::Redirect(_)
+        ));
+        assert!(f.get("https://example.com/Venue/2/").await.is_err());
+        assert_eq!(4, f.requests().len());
+    }
+}
@@ -0,0 +1,206 @@
+// Flags sites whose parser probably broke because the site changed its markup. A run is
+// compared with the site's recent runs in scrape_runs: a site that always had games and
+// suddenly has none, or whose share of unparseable events passes a threshold and is well
+// above the site's usual share, gets an alert. Alerts go to a JSON report file and/or a webhook.
+
+use crate::models::ScrapeRun;
+use anyhow::{Result, anyhow};
+
+pub struct HealthOptions {
+    /// runs of history to compare with
+    pub history: i64,
+    /// fewer ok runs than this is not enough history to call zero games a change
+    pub min_history: usize,
+    /// average games of the history above which zero games is flagged
+    pub min_average_games: f64,
+    /// share of parse errors among all parsed events, 0.0 to 1.0
+    pub max_error_rate: f64,
+    /// fewer parsed events than this are too few to judge the error rate by
+    pub min_events: usize,
+    /// how far above the history's average error rate a run must be to be flagged
+    pub error_rate_margin: f64,
+}
+
+impl Default for HealthOptions {
+    fn default() -> Self {
+        Self {
+            history: 10,
+            min_history: 3,
+            min_average_games: 3.0,
+            max_error_rate: 0.2,
+            min_events: 10,
+            error_rate_margin: 0.1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertReason {
+    NoGames,
+    ErrorRate,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct HealthAlert {
+    pub site: String,
+    pub reason: AlertReason,
+    pub message: String,
+    pub games_found: usize,
+    pub parse_errors: usize,
+    pub average_games: f64,
+    pub average_error_rate: f64,
+}
+
+fn error_rate(games: usize, errors: usize) -> f64 {
+    if games + errors == 0 {
+        return 0.0;
+    }
+    errors as f64 / (games + errors) as f64
+}
+
+/// Compares a run with `history`, the site's earlier runs. Failed runs are left out, a
+/// fetch error says nothing about the markup.
+pub fn check(
+    site: &str,
+    games_found: usize,
+    parse_errors: usize,
+    history: &[ScrapeRun],
+    opts: &HealthOptions,
+) -> Option<HealthAlert> {
+    let ok: Vec<&ScrapeRun> = history.iter().filter(|r| r.status == "ok").collect();
+    let (average_games, average_error_rate) = if ok.is_empty() {
+        (0.0, 0.0)
+    } else {
+        let n = ok.len() as f64;
+        (
+            ok.iter().map(|r| r.games_found as f64).sum::<f64>() / n,
+            ok.iter()
+                .map(|r| error_rate(r.games_found as usize, r.parse_errors as usize))
+                .sum::<f64>()
+                / n,
+        )
+    };
+
+    let rate = error_rate(games_found, parse_errors);
+    let (reason, message) = if games_found + parse_errors >= opts.min_events
+        && rate > opts.max_error_rate
+        && rate > average_error_rate + opts.error_rate_margin
+    {
+        (
+            AlertReason::ErrorRate,
+            format!(
+                "{} of {} events could not be parsed ({:.0}%, usually {:.0}%), the markup probably changed",
+                parse_errors,
+                games_found + parse_errors,
+                rate * 100.0,
+                average_error_rate * 100.0
+            ),
+        )
+    } else if games_found == 0
+        && ok.len() >= opts.min_history
+        && average_games >= opts.min_average_games
+    {
+        (
+            AlertReason::NoGames,
+            format!(
+                "no games found, {:.1} on average over the last {} runs, the markup probably changed",
+                average_games,
+                ok.len()
+            ),
+        )
+    } else {
+        return None;
+    };
+
+    Some(HealthAlert {
+        site: site.into(),
+        reason,
+        message,
+        games_found,
+        parse_errors,
+        average_games,
+        average_error_rate,
+    })
+}
+
+pub fn write_report(alerts: &[HealthAlert], w: impl std::io::Write) -> Result<()> {
+    serde_json::to_writer_pretty(w, alerts)?;
+    Ok(())
+}
+
+/// Posts `{"alerts": [...]}` to `url`, giving up after `notify::WEBHOOK_TIMEOUT`.
+pub async fn send_webhook(url: &str, alerts: &[HealthAlert]) -> Result<()> {
+    let res = crate::notify::webhook_client(crate::notify::WEBHOOK_TIMEOUT)
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(serde_json::to_vec(
+            &serde_json::json!({ "alerts": alerts }),
+        )?)
+        .send()
+        .await?;
+    if !res.status().is_success() {
+        return Err(anyhow!("webhook returned {}", res.status()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn run(games: i32, errors: i32, status: &str) -> ScrapeRun {
+        let at = chrono::NaiveDate::from_ymd_opt(2025, 11, 1)
+            .unwrap()
+            .and_hms_opt(6, 0, 0)
+            .unwrap();
+        ScrapeRun {
+            id: 0,
+            site: "whitby".into(),
+            started_at: at,
+            finished_at: at,
+            games_found: games,
+            parse_errors: errors,
+            status: status.into(),
+            error: None,
+            alert: None,
+        }
+    }
+
+    #[test]
+    fn test_check_flags_drop_to_zero() {
+        let history = vec![run(40, 0, "ok"), run(36, 1, "ok"), run(44, 0, "ok")];
+        let alert = check("whitby", 0, 0, &history, &HealthOptions::default()).unwrap();
+        assert_eq!(AlertReason::NoGames, alert.reason);
+        assert_eq!(40.0, alert.average_games);
+
+        // not enough history, or a site that rarely has games
+        assert!(check("whitby", 0, 0, &history[..2], &HealthOptions::default()).is_none());
+        let quiet = vec![run(1, 0, "ok"), run(0, 0, "ok"), run(2, 0, "ok")];
+        assert!(check("whitby", 0, 0, &quiet, &HealthOptions::default()).is_none());
+    }
+
+    #[test]
+    fn test_check_flags_error_rate() {
+        let history = vec![run(40, 0, "ok"), run(0, 0, "failed")];
+        let alert = check("whitby", 10, 30, &history, &HealthOptions::default()).unwrap();
+        assert_eq!(AlertReason::ErrorRate, alert.reason);
+        assert_eq!(40.0, alert.average_games);
+
+        assert!(check("whitby", 38, 2, &history, &HealthOptions::default()).is_none());
+    }
+
+    #[test]
+    fn test_check_error_rate_needs_volume_and_a_change() {
+        // 1 of 2 is half, but too few events to tell
+        let history = vec![run(40, 0, "ok")];
+        assert!(check("whitby", 1, 1, &history, &HealthOptions::default()).is_none());
+
+        // a site that always has about 30% unparseable events
+        let history = vec![run(35, 15, "ok"), run(28, 12, "ok"), run(42, 18, "ok")];
+        assert!(check("whitby", 33, 17, &history, &HealthOptions::default()).is_none());
+        let alert = check("whitby", 20, 30, &history, &HealthOptions::default()).unwrap();
+        assert_eq!(AlertReason::ErrorRate, alert.reason);
+        assert!((alert.average_error_rate - 0.3).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,253 @@
+// Minimal iCalendar (RFC 5545) writer for scraped schedules. Times are written as floating
+// local times since the sites publish them in the league's local time without a zone.
+
+use crate::models::EventWithVenue;
+use crate::site_scraper::ScrapedGame;
+use anyhow::Result;
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use std::io::Write;
+
+pub const DEFAULT_DURATION_MINUTES: i64 = 90;
+
+const PRODID: &str = "-//calendar-scraper//EN";
+const UID_DOMAIN: &str = "calendar-scraper";
+
+#[derive(Debug, Clone)]
+pub struct CalendarEvent {
+    pub uid: String,
+    pub start: NaiveDateTime,
+    pub duration: Duration,
+    pub summary: String,
+    pub location: String,
+    pub description: String,
+    pub stamp: NaiveDateTime,
+}
+
+impl From<&ScrapedGame> for CalendarEvent {
+    fn from(g: &ScrapedGame) -> Self {
+        CalendarEvent {
+            uid: game_uid(&g.site_name, g.date.date(), &g.home_team, &g.away_team),
+            start: g.date,
+            duration: Duration::minutes(DEFAULT_DURATION_MINUTES),
+            summary: format!("{} @ {}", g.away_team, g.home_team),
+            location: venue_location(&g.location, &g.address),
+            description: g.division.clone(),
+            stamp: chrono::Utc::now().naive_utc(),
+        }
+    }
+}
+
+impl From<&EventWithVenue> for CalendarEvent {
+    fn from(ev: &EventWithVenue) -> Self {
+        let e = &ev.event;
+
+        let location = match &ev.venue_name {
+            Some(name) => {
+                let mut name = name.clone();
+                if let Some(surface) = &ev.surface_name
+                    && surface != &name
+                {
+                    name = format!("{} - {}", name, surface);
+                }
+                let address = [&ev.venue_address, &ev.venue_city]
+                    .iter()
+                    .filter_map(|p| p.as_deref())
+                    .filter(|p| !p.is_empty())
+                    .collect::<Vec<&str>>()
+                    .join(", ");
+                venue_location(&name, &address)
+            }
+            _ => e.location.clone().unwrap_or_default(),
+        };
+
+        CalendarEvent {
+            uid: event_uid(&e.site, e.id),
+            start: e.datetime,
+            duration: Duration::minutes(DEFAULT_DURATION_MINUTES),
+            summary: format!("{} @ {}", e.guest_team, e.home_team),
+            location,
+            description: e.division.clone().unwrap_or_default(),
+            // stable, so a feed only changes when its events do
+            stamp: e.date_created,
+        }
+    }
+}
+
+/// UID of a scraped game that stays the same across exports, so subscribed calendars
+/// update events in place instead of duplicating them. It leaves out the start time, a
+/// game moved to another time of the same day keeps its UID.
+pub fn game_uid(site: &str, date: NaiveDate, home: &str, away: &str) -> String {
+    format!(
+        "{}-{}-{}-{}@{}",
+        slug(site),
+        date.format("%Y%m%d"),
+        slug(home),
+        slug(away),
+        UID_DOMAIN
+    )
+}
+
+/// UID of a stored event, from its row, which a sync updates in place when the game
+/// moves.
+pub fn event_uid(site: &str, id: crate::db::UnsignedBig) -> String {
+    format!("{}-event-{}@{}", slug(site), id, UID_DOMAIN)
+}
+
+/// "Rink name, address" or whichever of the two is known.
+pub fn venue_location(name: &str, address: &str) -> String {
+    let address = address.trim();
+    match (name.is_empty(), address.is_empty()) {
+        (false, false) => format!("{}, {}", name, address),
+        (false, true) => name.into(),
+        _ => address.into(),
+    }
+}
+
+pub fn write_calendar<'a>(
+    name: &str,
+    events: impl IntoIterator<Item = &'a CalendarEvent>,
+    mut w: impl Write,
+) -> Result<()> {
+    write_line(&mut w, "BEGIN:VCALENDAR")?;
+    write_line(&mut w, "VERSION:2.0")?;
+    write_line(&mut w, &format!("PRODID:{}", PRODID))?;
+    write_line(&mut w, "CALSCALE:GREGORIAN")?;
+    write_line(&mut w, "METHOD:PUBLISH")?;
+    write_line(&mut w, &format!("X-WR-CALNAME:{}", escape(name)))?;
+
+    for e in events {
+        write_line(&mut w, "BEGIN:VEVENT")?;
+        write_line(&mut w, &format!("UID:{}", e.uid))?;
+        write_line(
+            &mut w,
+            &format!("DTSTAMP:{}", e.stamp.format("%Y%m%dT%H%M%SZ")),
+        )?;
+        write_line(
+            &mut w,
+            &format!("DTSTART:{}", e.start.format("%Y%m%dT%H%M%S")),
+        )?;
+        let end = e.start + e.duration;
+        write_line(&mut w, &format!("DTEND:{}", end.format("%Y%m%dT%H%M%S")))?;
+        write_line(&mut w, &format!("SUMMARY:{}", escape(&e.summary)))?;
+        if !e.location.is_empty() {
+            write_line(&mut w, &format!("LOCATION:{}", escape(&e.location)))?;
+        }
+        if !e.description.is_empty() {
+            write_line(&mut w, &format!("DESCRIPTION:{}", escape(&e.description)))?;
+        }
+        write_line(&mut w, "END:VEVENT")?;
+    }
+
+    write_line(&mut w, "END:VCALENDAR")?;
+    w.flush()?;
+    Ok(())
+}
+
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            ';' => out.push_str("\\;"),
+            ',' => out.push_str("\\,"),
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+// content lines are folded at 75 octets, without splitting a utf-8 character
+fn write_line(w: &mut impl Write, line: &str) -> Result<()> {
+    let mut start = 0;
+    let mut limit = 75;
+    while line.len() - start > limit {
+        let mut end = start + limit;
+        while !line.is_char_boundary(end) {
+            end -= 1;
+        }
+        w.write_all(&line.as_bytes()[start..end])?;
+        w.write_all(b"\r\n ")?;
+        start = end;
+        // the leading space of a continuation line counts towards its length
+        limit = 74;
+    }
+    w.write_all(&line.as_bytes()[start..])?;
+    w.write_all(b"\r\n")?;
+    Ok(())
+}
+
+fn slug(s: &str) -> String {
+    s.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(|t| t.to_lowercase())
+        .collect::<Vec<String>>()
+        .join("-")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn game() -> ScrapedGame {
+        ScrapedGame {
+            date: NaiveDate::from_ymd_opt(2025, 11, 8)
+                .unwrap()
+                .and_hms_opt(19, 30, 0)
+                .unwrap(),
+            site_name: "whitby".into(),
+            site_display_name: Some("Whitby Minor Hockey".into()),
+            home_team: "Whitby Wildcats U13 AA".into(),
+            away_team: "Ajax Knights".into(),
+            location: "Iroquois Park Arena - Pad 1".into(),
+            division: "U13 AA".into(),
+            address_url: "".into(),
+            address: "500 Victoria St W, Whitby, ON".into(),
+        }
+    }
+
+    #[test]
+    fn test_write_calendar() {
+        let mut e = CalendarEvent::from(&game());
+        e.stamp = e.start;
+
+        let mut out = Vec::new();
+        write_calendar("whitby", [&e], &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap().replace("\r\n ", "");
+
+        assert!(out.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(out.ends_with("END:VCALENDAR\r\n"));
+        assert!(out.contains(
+            "UID:whitby-20251108-whitby-wildcats-u13-aa-ajax-knights@calendar-scraper\r\n"
+        ));
+        assert!(out.contains("DTSTART:20251108T193000\r\nDTEND:20251108T210000\r\n"));
+        assert!(out.contains("SUMMARY:Ajax Knights @ Whitby Wildcats U13 AA\r\n"));
+        assert!(out.contains(
+            "LOCATION:Iroquois Park Arena - Pad 1\\, 500 Victoria St W\\, Whitby\\, ON\r\n"
+        ));
+    }
+
+    #[test]
+    fn test_uid_survives_a_time_change() {
+        let mut moved = game();
+        moved.date += Duration::hours(2);
+        assert_eq!(
+            CalendarEvent::from(&game()).uid,
+            CalendarEvent::from(&moved).uid
+        );
+        assert_eq!("whitby-event-42@calendar-scraper", event_uid("whitby", 42));
+    }
+
+    #[test]
+    fn test_write_line_folds_long_lines() {
+        let line = format!("DESCRIPTION:{}", "é".repeat(60));
+        let mut out = Vec::new();
+        write_line(&mut out, &line).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        assert!(out.split("\r\n").all(|l| l.len() <= 75));
+        assert_eq!(line, out.replace("\r\n ", "").trim_end());
+    }
+}
@@ -1,21 +1,36 @@
 pub mod address_fetcher;
+pub mod api;
 pub mod client;
 pub mod cmdutils;
 pub mod config;
+pub mod db;
+pub mod dedup;
+pub mod division;
+pub mod fetcher;
+pub mod health;
+pub mod ics;
+pub mod logging;
+pub mod metrics;
 pub mod models;
+pub mod notify;
 pub mod repository;
+pub mod saved_pages;
+pub mod schedule_diff;
 pub mod schema;
+pub mod site_admin;
 pub mod site_scraper;
+pub mod vcr;
 pub mod venue_import;
 pub use repository::Repository;
 
+use db::DbConnection;
+use diesel::Connection;
 use diesel::prelude::*;
-use diesel::{Connection, MysqlConnection, mysql::Mysql};
 
 use diesel::r2d2::{ConnectionManager, Pool, R2D2Connection};
 
-pub fn connect_db(cfg: &config::AppConfig) -> MysqlConnection {
-    MysqlConnection::establish(&cfg.db_dsn).unwrap()
+pub fn connect_db(cfg: &config::AppConfig) -> DbConnection {
+    DbConnection::establish(&cfg.db_dsn).unwrap()
 }
 
 pub fn get_db_pool<Dbconnection: R2D2Connection + 'static>(
@@ -25,7 +40,7 @@ pub fn get_db_pool<Dbconnection: R2D2Connection + 'static>(
     Pool::builder().build(mgr).expect("failed to connect db")
 }
 
-pub fn insert_event(dbh: &mut MysqlConnection, e: &models::NewEvent) {
+pub fn insert_event(dbh: &mut DbConnection, e: &models::NewEvent) {
     diesel::insert_into(schema::events::table)
         .values(e)
         .execute(dbh)
@@ -34,7 +49,7 @@ pub fn insert_event(dbh: &mut MysqlConnection, e: &models::NewEvent) {
 
 pub fn get_sites<C>(conn: &mut C, sites: Vec<&str>) -> Vec<models::SitesConfig>
 where
-    C: Connection<Backend = Mysql> + diesel::connection::LoadConnection,
+    C: Connection<Backend = db::Backend> + diesel::connection::LoadConnection,
 {
     use schema::sites_config;
 
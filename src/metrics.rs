@@ -0,0 +1,201 @@
+// Prometheus metrics of the scraper. They live in one process wide registry so the HTTP
+// client, the address fetcher and the binaries can record without passing a handle
+// around; `serve` exposes them on /metrics and `scrape --metrics-file` writes them out
+// for node_exporter's textfile collector.
+
+use prometheus::{
+    Encoder, Gauge, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+use std::sync::LazyLock;
+use std::time::Duration;
+
+pub struct Metrics {
+    pub registry: Registry,
+    /// attempts, so a request retried twice counts three times
+    pub http_requests: IntCounterVec,
+    pub http_retries: IntCounterVec,
+    /// requests given up on after the last retry
+    pub http_failures: IntCounterVec,
+    pub http_request_duration: HistogramVec,
+    /// by status code, or "error" when no response came back
+    pub http_responses: IntCounterVec,
+    pub captcha_hits: IntCounterVec,
+    /// "hit" or "miss"
+    pub address_cache: IntCounterVec,
+    pub address_cache_hit_ratio: Gauge,
+    pub games_scraped: IntGaugeVec,
+    pub parse_errors: IntGaugeVec,
+    /// by what was imported, "games" or "locations"
+    pub db_import_duration: HistogramVec,
+}
+
+pub static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+fn counter(registry: &Registry, name: &str, help: &str, labels: &[&str]) -> IntCounterVec {
+    let c = IntCounterVec::new(Opts::new(name, help), labels).unwrap();
+    registry.register(Box::new(c.clone())).unwrap();
+    c
+}
+
+fn gauge(registry: &Registry, name: &str, help: &str, labels: &[&str]) -> IntGaugeVec {
+    let g = IntGaugeVec::new(Opts::new(name, help), labels).unwrap();
+    registry.register(Box::new(g.clone())).unwrap();
+    g
+}
+
+fn histogram(
+    registry: &Registry,
+    name: &str,
+    help: &str,
+    labels: &[&str],
+    buckets: Vec<f64>,
+) -> HistogramVec {
+    let h = HistogramVec::new(HistogramOpts::new(name, help).buckets(buckets), labels).unwrap();
+    registry.register(Box::new(h.clone())).unwrap();
+    h
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new_custom(Some("calscraper".into()), None).unwrap();
+        let address_cache_hit_ratio = Gauge::new(
+            "address_cache_hit_ratio",
+            "share of venue address lookups answered from the cache",
+        )
+        .unwrap();
+        registry
+            .register(Box::new(address_cache_hit_ratio.clone()))
+            .unwrap();
+
+        Self {
+            http_requests: counter(
+                &registry,
+                "http_requests_total",
+                "HTTP request attempts",
+                &["host"],
+            ),
+            http_retries: counter(
+                &registry,
+                "http_retries_total",
+                "HTTP requests retried after an error or a 5xx response",
+                &["host"],
+            ),
+            http_failures: counter(
+                &registry,
+                "http_failures_total",
+                "HTTP requests that failed after all retries",
+                &["host"],
+            ),
+            http_request_duration: histogram(
+                &registry,
+                "http_request_duration_seconds",
+                "HTTP request latency",
+                &["host"],
+                vec![0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0],
+            ),
+            http_responses: counter(
+                &registry,
+                "http_responses_total",
+                "HTTP responses by status code",
+                &["host", "status"],
+            ),
+            captcha_hits: counter(
+                &registry,
+                "captcha_hits_total",
+                "redirects to a captcha page",
+                &["host"],
+            ),
+            address_cache: counter(
+                &registry,
+                "address_cache_lookups_total",
+                "venue address lookups by cache hit or miss",
+                &["result"],
+            ),
+            address_cache_hit_ratio,
+            games_scraped: gauge(
+                &registry,
+                "games_scraped",
+                "games found by the last scrape of a site",
+                &["site"],
+            ),
+            parse_errors: gauge(
+                &registry,
+                "parse_errors",
+                "events the last scrape of a site could not parse",
+                &["site"],
+            ),
+            db_import_duration: histogram(
+                &registry,
+                "db_import_duration_seconds",
+                "time to import scraped rows into the database",
+                &["kind"],
+                vec![0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0],
+            ),
+            registry,
+        }
+    }
+
+    pub fn observe_response(&self, host: &str, status: Option<u16>, elapsed: Duration) {
+        let status = status.map_or("error".to_string(), |s| s.to_string());
+        self.http_responses
+            .with_label_values(&[host, status.as_str()])
+            .inc();
+        self.http_request_duration
+            .with_label_values(&[host])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    pub fn address_lookup(&self, hit: bool) {
+        self.address_cache
+            .with_label_values(&[if hit { "hit" } else { "miss" }])
+            .inc();
+        let hits = self.address_cache.with_label_values(&["hit"]).get();
+        let misses = self.address_cache.with_label_values(&["miss"]).get();
+        self.address_cache_hit_ratio
+            .set(hits as f64 / (hits + misses) as f64);
+    }
+
+    /// The metrics in the Prometheus text format.
+    pub fn render(&self) -> String {
+        let mut buf = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buf)
+            .unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+}
+
+/// Host part of `url` for labels, "unknown" when it has none.
+pub fn host_label(url: &str) -> String {
+    url::Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(String::from))
+        .unwrap_or_else(|| "unknown".into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let m = Metrics::new();
+        m.http_requests.with_label_values(&["example.com"]).inc();
+        m.observe_response("example.com", Some(503), Duration::from_millis(300));
+        m.address_lookup(true);
+        m.address_lookup(true);
+        m.address_lookup(false);
+        m.address_lookup(true);
+
+        let text = m.render();
+        assert!(text.contains("calscraper_http_requests_total{host=\"example.com\"} 1"));
+        assert!(
+            text.contains("calscraper_http_responses_total{host=\"example.com\",status=\"503\"} 1")
+        );
+        assert!(text.contains(
+            "calscraper_http_request_duration_seconds_bucket{host=\"example.com\",le=\"0.5\"} 1"
+        ));
+        assert!(text.contains("calscraper_address_cache_hit_ratio 0.75"));
+    }
+}
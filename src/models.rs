@@ -1,9 +1,9 @@
-use diesel::{Insertable, Queryable, Selectable};
+use diesel::{AsChangeset, Insertable, Queryable, Selectable};
 use std::collections::hash_map::HashMap;
 
-#[derive(Debug, Queryable, Selectable)]
+#[derive(Debug, Queryable, Selectable, serde::Serialize)]
 #[diesel(table_name=crate::schema::sites_config)]
-#[diesel(check_for_backend(diesel::mysql::Mysql))]
+#[diesel(check_for_backend(crate::db::Backend))]
 pub struct SitesConfig {
     pub id: i32,
     pub site_name: String,
@@ -20,7 +20,7 @@ pub struct SitesConfig {
     pub updated_at: Option<chrono::NaiveDateTime>,
 }
 
-#[derive(Insertable)]
+#[derive(Debug, Insertable, serde::Deserialize)]
 #[diesel(table_name=crate::schema::sites_config)]
 pub struct NewSitesConfig {
     pub site_name: String,
@@ -34,11 +34,25 @@ pub struct NewSitesConfig {
     pub notes: Option<String>,
 }
 
-#[derive(Debug, Queryable, Selectable)]
+/// Fields of a site that can be changed, None leaves the column as it is.
+#[derive(Debug, Default, AsChangeset, serde::Deserialize)]
+#[diesel(table_name=crate::schema::sites_config)]
+pub struct SitesConfigChanges {
+    pub display_name: Option<String>,
+    pub base_url: Option<String>,
+    pub home_team: Option<String>,
+    pub parser_type: Option<String>,
+    pub parser_config: Option<serde_json::Value>,
+    pub enabled: Option<bool>,
+    pub scrape_frequency_hours: Option<i32>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Queryable, Selectable, serde::Serialize)]
 #[diesel(table_name=crate::schema::events)]
-#[diesel(check_for_backend(diesel::mysql::Mysql))]
+#[diesel(check_for_backend(crate::db::Backend))]
 pub struct Event {
-    pub id: u64,
+    pub id: crate::db::UnsignedBig,
     pub site: String,
     pub source_type: Option<String>,
     pub datetime: chrono::NaiveDateTime,
@@ -51,11 +65,28 @@ pub struct Event {
     pub location_id: Option<i32>,
     pub surface_id: i32,
     pub date_created: chrono::NaiveDateTime,
+    pub age_group: Option<String>,
+    pub tier: Option<String>,
+    pub gender: Option<String>,
+    pub season_type: Option<String>,
+    pub canonical_id: Option<crate::db::UnsignedBig>,
+    /// set when a sync of the site no longer found the game, see `schedule_diff::plan_sync`
+    pub deleted_at: Option<chrono::NaiveDateTime>,
+}
+
+/// An event with its mapped venue, when `location_id` and `surface_id` are known.
+#[derive(Debug)]
+pub struct EventWithVenue {
+    pub event: Event,
+    pub venue_name: Option<String>,
+    pub venue_address: Option<String>,
+    pub venue_city: Option<String>,
+    pub surface_name: Option<String>,
 }
 
 #[derive(Debug, Insertable)]
 #[diesel(table_name=crate::schema::events)]
-#[diesel(check_for_backend(diesel::mysql::Mysql))]
+#[diesel(check_for_backend(crate::db::Backend))]
 pub struct InsertEvent {
     pub site: String,
     pub datetime: chrono::NaiveDateTime,
@@ -66,13 +97,51 @@ pub struct InsertEvent {
     pub location_id: Option<i32>,
     pub surface_id: i32,
     // pub date_created: chrono::NaiveDateTime,
+    pub age_group: Option<String>,
+    pub tier: Option<String>,
+    pub gender: Option<String>,
+    pub season_type: Option<String>,
+}
+
+/// The scraped fields of an event, written over a stored row when a sync finds the
+/// game changed. A None clears the column, and `deleted_at` None restores a removed row.
+#[derive(Debug, AsChangeset)]
+#[diesel(table_name=crate::schema::events, treat_none_as_null = true)]
+pub struct EventChanges {
+    pub datetime: chrono::NaiveDateTime,
+    pub home_team: String,
+    pub guest_team: String,
+    pub location: Option<String>,
+    pub division: Option<String>,
+    pub age_group: Option<String>,
+    pub tier: Option<String>,
+    pub gender: Option<String>,
+    pub season_type: Option<String>,
+    pub deleted_at: Option<chrono::NaiveDateTime>,
+}
+
+impl From<&InsertEvent> for EventChanges {
+    fn from(e: &InsertEvent) -> Self {
+        Self {
+            datetime: e.datetime,
+            home_team: e.home_team.clone(),
+            guest_team: e.guest_team.clone(),
+            location: e.location.clone(),
+            division: e.division.clone(),
+            age_group: e.age_group.clone(),
+            tier: e.tier.clone(),
+            gender: e.gender.clone(),
+            season_type: e.season_type.clone(),
+            deleted_at: None,
+        }
+    }
 }
 
 #[derive(Debug, Queryable, Selectable)]
 #[diesel(table_name=crate::schema::events)]
-#[diesel(check_for_backend(diesel::mysql::Mysql))]
+#[diesel(check_for_backend(crate::db::Backend))]
 pub struct EventSmall {
-    pub id: u64,
+    pub id: crate::db::UnsignedBig,
     pub site: String,
     pub datetime: chrono::NaiveDateTime,
     pub home_team: String,
@@ -93,6 +162,36 @@ pub struct NewEvent {
     pub location_id: Option<i32>,
 }
 
+/// One site of one scrape run, kept to notice parser breakage, see `health`.
+#[derive(Debug, Clone, Queryable, Selectable, serde::Serialize)]
+#[diesel(table_name=crate::schema::scrape_runs)]
+#[diesel(check_for_backend(crate::db::Backend))]
+pub struct ScrapeRun {
+    pub id: crate::db::UnsignedBig,
+    pub site: String,
+    pub started_at: chrono::NaiveDateTime,
+    pub finished_at: chrono::NaiveDateTime,
+    pub games_found: i32,
+    pub parse_errors: i32,
+    /// "ok" or "failed"
+    pub status: String,
+    pub error: Option<String>,
+    pub alert: Option<String>,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name=crate::schema::scrape_runs)]
+pub struct NewScrapeRun {
+    pub site: String,
+    pub started_at: chrono::NaiveDateTime,
+    pub finished_at: chrono::NaiveDateTime,
+    pub games_found: i32,
+    pub parse_errors: i32,
+    pub status: String,
+    pub error: Option<String>,
+    pub alert: Option<String>,
+}
+
 #[derive(Insertable)]
 #[diesel(table_name=crate::schema::provinces)]
 pub struct Provnice {
@@ -133,12 +232,55 @@ pub struct Surface {
     pub uuid: String,
     pub orderIndex: i32,
     pub venue_id: i32,
-    pub closed_from: u64,
+    pub closed_from: crate::db::UnsignedBig,
+    pub coming_soon: bool,
+    pub online: bool,
+    pub status: String,
+    pub sports: String,
+    pub first_media_date: crate::db::UnsignedBig,
+}
+
+#[derive(Debug, Queryable, Selectable, serde::Serialize)]
+#[diesel(table_name=crate::schema::locations)]
+#[diesel(check_for_backend(crate::db::Backend))]
+pub struct LocationRow {
+    pub id: i32,
+    pub address1: Option<String>,
+    pub address2: Option<String>,
+    pub city: Option<String>,
+    pub name: String,
+    pub uuid: Option<String>,
+    pub recording_hours_local: Option<String>,
+    pub postal_code: Option<String>,
+    pub all_sheets_count: Option<i32>,
+    pub longitude: Option<f32>,
+    pub latitude: Option<f32>,
+    pub logo_url: Option<String>,
+    pub province_id: Option<i32>,
+    pub venue_status: Option<String>,
+    pub zone: Option<String>,
+    pub total_surfaces: Option<i32>,
+    pub deleted_at: Option<chrono::NaiveDateTime>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Queryable, Selectable, serde::Serialize)]
+#[diesel(table_name=crate::schema::surfaces)]
+#[diesel(check_for_backend(crate::db::Backend))]
+pub struct SurfaceRow {
+    pub id: i32,
+    pub location_id: i32,
+    pub name: String,
+    pub uuid: String,
+    pub orderIndex: i32,
+    pub venue_id: i32,
+    pub closed_from: crate::db::UnsignedBig,
     pub coming_soon: bool,
     pub online: bool,
     pub status: String,
     pub sports: String,
-    pub first_media_date: u64,
+    pub first_media_date: crate::db::UnsignedBig,
+    pub deleted_at: Option<chrono::NaiveDateTime>,
 }
 
 #[allow(non_snake_case)]
@@ -234,3 +376,17 @@ pub struct SitesLocation {
     pub match_type: Option<String>,
     pub surface_id: i32,
 }
+
+#[derive(Debug, Queryable, Selectable, serde::Serialize)]
+#[diesel(table_name=crate::schema::sites_locations)]
+#[diesel(check_for_backend(crate::db::Backend))]
+pub struct SitesLocationRow {
+    pub site: String,
+    pub location: String,
+    pub location_id: Option<i32>,
+    pub loc: Option<String>,
+    pub surface: Option<String>,
+    pub address: Option<String>,
+    pub match_type: Option<String>,
+    pub surface_id: i32,
+}
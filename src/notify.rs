@@ -0,0 +1,523 @@
+// Delivers schedule changes found by an import to the sinks people subscribed to: an
+// HTTP webhook with a signed JSON payload, or an email over SMTP. For local testing
+// point a webhook at any HTTP server and the SMTP settings at a mail catcher such as
+// mailpit with `tls: false`.
+
+use crate::logging::redact_url;
+use crate::schedule_diff::ScheduleChange;
+use anyhow::{Result, anyhow};
+use hmac::{Hmac, Mac};
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::Deserialize;
+use std::fmt;
+
+/// Header with the hex HMAC-SHA256 of the body, keyed with the webhook secret, as
+/// `sha256=<hex>`.
+pub const SIGNATURE_HEADER: &str = "x-signature-256";
+
+#[derive(Debug, Default, Deserialize)]
+pub struct NotifyConfig {
+    #[serde(default)]
+    pub sinks: Vec<SinkConfig>,
+    pub smtp: Option<SmtpConfig>,
+    #[serde(default)]
+    pub subscriptions: Vec<Subscription>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SinkConfig {
+    Webhook {
+        name: String,
+        url: String,
+        secret: Option<String>,
+    },
+    Email {
+        name: String,
+        to: Vec<String>,
+    },
+}
+
+#[derive(Deserialize)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: Option<u16>,
+    pub from: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// false for a local mail catcher
+    #[serde(default = "default_tls")]
+    pub tls: bool,
+}
+
+fn default_tls() -> bool {
+    true
+}
+
+// secrets stay out of logs
+impl fmt::Debug for SinkConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SinkConfig::Webhook { name, url, secret } => f
+                .debug_struct("Webhook")
+                .field("name", name)
+                .field("url", &redact_url(url))
+                .field("secret", &secret.as_ref().map(|_| "***"))
+                .finish(),
+            SinkConfig::Email { name, to } => f
+                .debug_struct("Email")
+                .field("name", name)
+                .field("to", to)
+                .finish(),
+        }
+    }
+}
+
+impl fmt::Debug for SmtpConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SmtpConfig")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("from", &self.from)
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "***"))
+            .field("tls", &self.tls)
+            .finish()
+    }
+}
+
+/// Sends the changes a sink subscribed to. Filters left out match everything, so a
+/// subscription with only `sink` gets every change.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Subscription {
+    pub sink: String,
+    pub site: Option<String>,
+    /// matched anywhere in the home or guest team, ignoring case
+    pub team: Option<String>,
+    /// matched anywhere in the rink before or after the change, ignoring case
+    pub venue: Option<String>,
+}
+
+fn contains(haystack: &str, needle: &str) -> bool {
+    haystack.to_lowercase().contains(&needle.to_lowercase())
+}
+
+impl Subscription {
+    pub fn matches(&self, change: &ScheduleChange) -> bool {
+        if let Some(site) = &self.site
+            && site != &change.site
+        {
+            return false;
+        }
+        if let Some(team) = &self.team
+            && !contains(&change.home_team, team)
+            && !contains(&change.guest_team, team)
+        {
+            return false;
+        }
+        if let Some(venue) = &self.venue {
+            let rinks = [&change.location, &change.previous_location];
+            if !rinks
+                .iter()
+                .any(|r| r.as_deref().is_some_and(|r| contains(r, venue)))
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait::async_trait]
+pub trait NotificationSink: Send + Sync {
+    async fn send(&self, changes: &[ScheduleChange]) -> Result<()>;
+}
+
+/// Signs `body` with `secret`, the value of [`SIGNATURE_HEADER`].
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<sha2::Sha256>::new_from_slice(secret.as_bytes()).expect("hmac takes any key size");
+    mac.update(body);
+    let hex: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    format!("sha256={}", hex)
+}
+
+/// How long a webhook post may take, so a hanging receiver cannot hold up the end of a
+/// scrape run.
+pub const WEBHOOK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Client for posting to webhooks, giving up on a post after `timeout`.
+pub fn webhook_client(timeout: std::time::Duration) -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(timeout)
+        .connect_timeout(timeout)
+        .build()
+        .expect("webhook client")
+}
+
+/// Posts `{"changes": [...]}`.
+pub struct WebhookSink {
+    url: String,
+    secret: Option<String>,
+    client: reqwest::Client,
+}
+
+impl WebhookSink {
+    pub fn new(url: &str, secret: Option<String>) -> Self {
+        Self {
+            url: url.into(),
+            secret,
+            client: webhook_client(WEBHOOK_TIMEOUT),
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.client = webhook_client(timeout);
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl NotificationSink for WebhookSink {
+    async fn send(&self, changes: &[ScheduleChange]) -> Result<()> {
+        let body = serde_json::to_vec(&serde_json::json!({ "changes": changes }))?;
+        let mut req = self
+            .client
+            .post(&self.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json");
+        if let Some(secret) = &self.secret {
+            req = req.header(SIGNATURE_HEADER, sign(secret, &body));
+        }
+        let res = req.body(body).send().await?;
+        if !res.status().is_success() {
+            return Err(anyhow!("webhook {} returned {}", self.url, res.status()));
+        }
+        Ok(())
+    }
+}
+
+/// Mails one plain text message per import to all of `to`. Generic over the transport
+/// so tests can use lettre's stub transport.
+pub struct EmailSink<T> {
+    transport: T,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+}
+
+impl<T> EmailSink<T> {
+    pub fn new(transport: T, from: &str, to: &[String]) -> Result<Self> {
+        Ok(Self {
+            transport,
+            from: from.parse()?,
+            to: to.iter().map(|a| a.parse()).collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl EmailSink<AsyncSmtpTransport<Tokio1Executor>> {
+    pub fn smtp(cfg: &SmtpConfig, to: &[String]) -> Result<Self> {
+        let mut builder = if cfg.tls {
+            AsyncSmtpTransport::<Tokio1Executor>::relay(&cfg.host)?
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&cfg.host)
+        };
+        if let Some(port) = cfg.port {
+            builder = builder.port(port);
+        }
+        if let (Some(user), Some(password)) = (&cfg.username, &cfg.password) {
+            builder = builder.credentials(Credentials::new(user.clone(), password.clone()));
+        }
+        Self::new(builder.build(), &cfg.from, to)
+    }
+}
+
+fn email_subject(changes: &[ScheduleChange]) -> String {
+    let mut sites: Vec<&str> = changes.iter().map(|c| c.site.as_str()).collect();
+    sites.sort();
+    sites.dedup();
+    format!(
+        "{} schedule change{} ({})",
+        changes.len(),
+        if changes.len() == 1 { "" } else { "s" },
+        sites.join(", ")
+    )
+}
+
+#[async_trait::async_trait]
+impl<T> NotificationSink for EmailSink<T>
+where
+    T: AsyncTransport + Send + Sync,
+    T::Error: std::error::Error + Send + Sync + 'static,
+{
+    async fn send(&self, changes: &[ScheduleChange]) -> Result<()> {
+        let mut builder = Message::builder()
+            .from(self.from.clone())
+            .subject(email_subject(changes));
+        for to in &self.to {
+            builder = builder.to(to.clone());
+        }
+        let text: Vec<String> = changes.iter().map(|c| c.describe()).collect();
+        let message = builder.body(text.join("\n"))?;
+        self.transport.send(message).await?;
+        Ok(())
+    }
+}
+
+pub struct Notifier {
+    sinks: Vec<(String, Box<dyn NotificationSink>)>,
+    subscriptions: Vec<Subscription>,
+}
+
+impl Notifier {
+    pub fn new(subscriptions: Vec<Subscription>) -> Self {
+        Self {
+            sinks: Vec::new(),
+            subscriptions,
+        }
+    }
+
+    pub fn with_sink(mut self, name: &str, sink: Box<dyn NotificationSink>) -> Self {
+        self.sinks.push((name.into(), sink));
+        self
+    }
+
+    pub fn from_config(cfg: NotifyConfig) -> Result<Self> {
+        let mut notifier = Self::new(cfg.subscriptions);
+        for sink in cfg.sinks {
+            notifier = match sink {
+                SinkConfig::Webhook { name, url, secret } => {
+                    notifier.with_sink(&name, Box::new(WebhookSink::new(&url, secret)))
+                }
+                SinkConfig::Email { name, to } => {
+                    let smtp = cfg
+                        .smtp
+                        .as_ref()
+                        .ok_or_else(|| anyhow!("email sink {} needs smtp settings", name))?;
+                    notifier.with_sink(&name, Box::new(EmailSink::smtp(smtp, &to)?))
+                }
+            };
+        }
+        for sub in &notifier.subscriptions {
+            if !notifier.sinks.iter().any(|(name, _)| name == &sub.sink) {
+                return Err(anyhow!("subscription to unknown sink {}", sub.sink));
+            }
+        }
+        Ok(notifier)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.subscriptions.is_empty()
+    }
+
+    /// Sends each sink the changes its subscriptions match. All sinks are tried, a
+    /// failing sink is reported in the error.
+    pub async fn notify(&self, changes: &[ScheduleChange]) -> Result<()> {
+        let mut failed = Vec::new();
+        for (name, sink) in &self.sinks {
+            let subs: Vec<&Subscription> = self
+                .subscriptions
+                .iter()
+                .filter(|s| &s.sink == name)
+                .collect();
+            let matched: Vec<ScheduleChange> = changes
+                .iter()
+                .filter(|c| subs.iter().any(|s| s.matches(c)))
+                .cloned()
+                .collect();
+            if matched.is_empty() {
+                continue;
+            }
+            if let Err(e) = sink.send(&matched).await {
+                failed.push(format!("{}: {:#}", name, e));
+            }
+        }
+        if !failed.is_empty() {
+            return Err(anyhow!("notification failed, {}", failed.join("; ")));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::schedule_diff::ChangeKind;
+
+    fn change(site: &str, home: &str, guest: &str, rink: &str) -> ScheduleChange {
+        ScheduleChange {
+            kind: ChangeKind::Updated,
+            site: site.into(),
+            home_team: home.into(),
+            guest_team: guest.into(),
+            datetime: chrono::NaiveDate::from_ymd_opt(2025, 11, 1)
+                .unwrap()
+                .and_hms_opt(18, 0, 0)
+                .unwrap(),
+            location: Some(rink.into()),
+            division: None,
+            previous_datetime: None,
+            previous_location: Some("Iroquois Park".into()),
+        }
+    }
+
+    #[test]
+    fn test_sign() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+            sign("Jefe", b"what do ya want for nothing?")
+        );
+    }
+
+    #[test]
+    fn test_subscription_matches() {
+        let c = change("whitby", "Whitby Wildcats", "Ajax Knights", "McKinney");
+        let sub = |site: Option<&str>, team: Option<&str>, venue: Option<&str>| Subscription {
+            sink: "coaches".into(),
+            site: site.map(Into::into),
+            team: team.map(Into::into),
+            venue: venue.map(Into::into),
+        };
+
+        assert!(sub(None, None, None).matches(&c));
+        assert!(sub(Some("whitby"), Some("ajax"), None).matches(&c));
+        assert!(!sub(Some("ajax"), None, None).matches(&c));
+        assert!(!sub(None, Some("oshawa"), None).matches(&c));
+        // the rink the game moved away from counts too
+        assert!(sub(None, None, Some("iroquois")).matches(&c));
+        assert!(!sub(None, None, Some("civic")).matches(&c));
+    }
+
+    #[tokio::test]
+    async fn test_notify_routes_changes_to_subscribed_sinks() {
+        let changes = vec![
+            change("whitby", "Whitby Wildcats", "Ajax Knights", "McKinney"),
+            change("oshawa", "Oshawa Generals", "Clarington Toros", "Civic"),
+        ];
+
+        let mut whitby = MockNotificationSink::new();
+        whitby
+            .expect_send()
+            .withf(|c| c.len() == 1 && c[0].site == "whitby")
+            .times(1)
+            .returning(|_| Ok(()));
+        let mut failing = MockNotificationSink::new();
+        failing
+            .expect_send()
+            .times(1)
+            .returning(|_| Err(anyhow!("connection refused")));
+        let mut unsubscribed = MockNotificationSink::new();
+        unsubscribed.expect_send().never();
+
+        let notifier = Notifier::new(vec![
+            Subscription {
+                sink: "whitby".into(),
+                site: Some("whitby".into()),
+                ..Default::default()
+            },
+            Subscription {
+                sink: "failing".into(),
+                ..Default::default()
+            },
+        ])
+        .with_sink("whitby", Box::new(whitby))
+        .with_sink("failing", Box::new(failing))
+        .with_sink("unsubscribed", Box::new(unsubscribed));
+
+        let err = notifier.notify(&changes).await.unwrap_err();
+        assert!(err.to_string().contains("failing: connection refused"));
+    }
+
+    #[tokio::test]
+    async fn test_email_sink() {
+        let transport = lettre::transport::stub::AsyncStubTransport::new_ok();
+        let sink = EmailSink::new(
+            transport.clone(),
+            "Schedules <schedules@example.com>",
+            &["coach@example.com".into()],
+        )
+        .unwrap();
+        let c = change("whitby", "Whitby Wildcats", "Ajax Knights", "McKinney");
+        sink.send(&[c]).await.unwrap();
+
+        let sent = transport.messages().await;
+        assert_eq!(1, sent.len());
+        assert_eq!("coach@example.com", sent[0].0.to()[0].to_string());
+        assert!(sent[0].1.contains("Subject: 1 schedule change (whitby)"));
+        assert!(sent[0].1.contains("Whitby Wildcats vs Ajax Knights"));
+    }
+
+    // a local receiver that keeps the signature header and body of each post
+    async fn webhook_receiver() -> (
+        String,
+        tokio::sync::mpsc::UnboundedReceiver<(String, Vec<u8>)>,
+    ) {
+        use axum::http::{HeaderMap, StatusCode};
+
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let app = axum::Router::new()
+            .route(
+                "/hook",
+                axum::routing::post(move |headers: HeaderMap, body: axum::body::Bytes| {
+                    let signature = headers
+                        .get(SIGNATURE_HEADER)
+                        .map(|v| v.to_str().unwrap().to_string())
+                        .unwrap_or_default();
+                    tx.send((signature, body.to_vec())).unwrap();
+                    async { StatusCode::NO_CONTENT }
+                }),
+            )
+            .route(
+                "/down",
+                axum::routing::post(|| async { StatusCode::INTERNAL_SERVER_ERROR }),
+            )
+            .route(
+                "/hang",
+                axum::routing::post(|| async {
+                    tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+                    StatusCode::NO_CONTENT
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (format!("http://{}", addr), rx)
+    }
+
+    #[tokio::test]
+    async fn test_webhook_sink() {
+        let (base_url, mut received) = webhook_receiver().await;
+        let c = change("whitby", "Whitby Wildcats", "Ajax Knights", "McKinney");
+
+        let sink = WebhookSink::new(&format!("{}/hook", base_url), Some("s3cret".into()));
+        sink.send(std::slice::from_ref(&c)).await.unwrap();
+        let (signature, body) = received.recv().await.unwrap();
+        assert_eq!(sign("s3cret", &body), signature);
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(serde_json::json!({ "changes": [c] }), payload);
+
+        // no secret, no signature
+        let sink = WebhookSink::new(&format!("{}/hook", base_url), None);
+        sink.send(std::slice::from_ref(&c)).await.unwrap();
+        assert_eq!("", received.recv().await.unwrap().0);
+
+        let sink = WebhookSink::new(&format!("{}/down", base_url), None);
+        let err = sink.send(std::slice::from_ref(&c)).await.unwrap_err();
+        assert!(err.to_string().contains("500"));
+
+        let sink = WebhookSink::new(&format!("{}/hang", base_url), None)
+            .with_timeout(std::time::Duration::from_millis(200));
+        let started = std::time::Instant::now();
+        let err = sink.send(&[c]).await.unwrap_err();
+        assert!(format!("{:#}", err).contains("timed out"), "{:#}", err);
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+    }
+}
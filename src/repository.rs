@@ -1,20 +1,131 @@
+use crate::db::{self, DbConnection};
+use crate::dedup;
+use crate::metrics::METRICS;
 use crate::models;
+use crate::schedule_diff::{self, ScheduleChange};
 use crate::schema;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use diesel::Connection;
 use diesel::prelude::*;
-use diesel::{Connection, MysqlConnection};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
 
 use diesel::r2d2::{ConnectionManager, Pool, R2D2Connection};
 
+/// Writes of the scraper. The calls are async so an import does not block the runtime
+/// threads that fetch pages. Each call imports the rows of one site in one transaction,
+/// so a failed import leaves none of them behind.
+///
+/// A scrape imports its locations and its games in two calls, so two transactions: the
+/// locations are imported by `Scraper::process_site` while the games are still to be
+/// compared with the stored ones. Locations are only ever added, ignoring known ones, so
+/// those left behind by a failed game import are the ones the next scrape adds anyway.
 #[cfg_attr(test, mockall::automock)]
+#[async_trait::async_trait]
 pub trait RepositoryOps {
-    fn import_locations(
+    async fn import_locations(
         &self,
-        _site_name: &str,
+        site_name: &str,
         locations: Vec<models::SitesLocation>,
     ) -> Result<()>;
-    fn import_games(&self, games: Vec<models::InsertEvent>) -> Result<()>;
+    async fn import_games(&self, games: Vec<models::InsertEvent>) -> Result<()>;
+    /// Rewrites the site's events from `from` up to `to` to match `games`, a full scrape
+    /// of those days, see `schedule_diff::plan_sync`. Games outside the window are only
+    /// added. Fails, importing nothing, when the sync would cancel more than
+    /// `max_cancelled_share` of the stored games.
+    async fn sync_games(
+        &self,
+        site_name: &str,
+        from: chrono::NaiveDateTime,
+        to: chrono::NaiveDateTime,
+        games: Vec<models::InsertEvent>,
+        max_cancelled_share: f64,
+    ) -> Result<Vec<ScheduleChange>>;
+}
+
+/// Reads of the events, shared by the API, the feeds and the scraper's change notices.
+/// Like `RepositoryOps` the calls run on the blocking pool.
+#[cfg_attr(test, mockall::automock)]
+#[async_trait::async_trait]
+pub trait EventQueries {
+    /// One page of the events matching `filter` in start order, and the number of matches.
+    async fn find_events(
+        &self,
+        filter: EventFilter,
+        page: Page,
+    ) -> Result<(Vec<models::Event>, i64)>;
+    /// At most `limit` events matching `filter` with their mapped venue, in start order.
+    async fn find_events_with_venue(
+        &self,
+        filter: EventFilter,
+        limit: i64,
+    ) -> Result<Vec<models::EventWithVenue>>;
+    /// The events matching `filter` counted per value of one column.
+    async fn count_events(&self, filter: EventFilter, by: CountBy) -> Result<Vec<EventCount>>;
+    /// The site's events from `from` to `to` inclusive, duplicates too.
+    async fn site_events_between(
+        &self,
+        site: &str,
+        from: chrono::NaiveDateTime,
+        to: chrono::NaiveDateTime,
+    ) -> Result<Vec<models::Event>>;
+}
+
+/// Rows per INSERT statement of an import, see IMPORT_CHUNK_SIZE in config.rs.
+pub const DEFAULT_CHUNK_SIZE: usize = 500;
+
+/// Share of a site's stored games a sync may cancel before it takes the scrape for
+/// truncated, see `RepositoryOps::sync_games`.
+pub const DEFAULT_MAX_CANCELLED_SHARE: f64 = 0.25;
+
+#[derive(Debug, Default, Clone)]
+pub struct EventFilter {
+    pub site: Option<String>,
+    pub from: Option<chrono::NaiveDateTime>,
+    pub to: Option<chrono::NaiveDateTime>,
+    /// matched anywhere in the division text
+    pub division: Option<String>,
+    pub age_group: Option<String>,
+    pub tier: Option<String>,
+    pub gender: Option<String>,
+    /// matched anywhere in the home or guest team name
+    pub team: Option<String>,
+    pub location_id: Option<i32>,
+    pub surface_id: Option<i32>,
+    /// include events linked to a canonical event on another site
+    pub include_duplicates: bool,
+}
+
+/// The column `count_events` groups by.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CountBy {
+    Site,
+    Division,
+    AgeGroup,
+    Location,
+    Surface,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct EventCount {
+    /// the column value, location and surface ids as text; None for events without one
+    pub key: Option<String>,
+    pub events: i64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Page {
+    /// 1 based
+    pub page: i64,
+    pub per_page: i64,
+}
+
+impl Page {
+    fn offset(&self) -> i64 {
+        (self.page - 1) * self.per_page
+    }
 }
 
 pub struct Repository<T>
@@ -22,15 +133,66 @@ where
     T: Connection + R2D2Connection + 'static,
 {
     pool: Pool<ConnectionManager<T>>,
+    // one per pooled connection, see `blocking`
+    permits: Arc<Semaphore>,
+    chunk_size: usize,
 }
 
-impl Repository<diesel::MysqlConnection> {
-    pub fn new(dsn: &str) -> Self {
-        let mgr = ConnectionManager::<MysqlConnection>::new(dsn);
+impl<T> Clone for Repository<T>
+where
+    T: Connection + R2D2Connection + 'static,
+{
+    fn clone(&self) -> Self {
         Self {
-            pool: Pool::builder().build(mgr).expect("failed to connect db"),
+            pool: self.pool.clone(),
+            permits: Arc::clone(&self.permits),
+            chunk_size: self.chunk_size,
         }
     }
+}
+
+impl Repository<DbConnection> {
+    pub fn new(dsn: &str) -> Result<Self> {
+        let mgr = ConnectionManager::<DbConnection>::new(dsn);
+        let builder = Pool::builder();
+        #[cfg(feature = "sqlite")]
+        let builder = builder.connection_customizer(Box::new(db::SqliteSetup));
+        let pool = builder
+            .build(mgr)
+            .context("failed to connect to the database")?;
+        let permits = Arc::new(Semaphore::new(pool.max_size() as usize));
+        Ok(Self {
+            pool,
+            permits,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+        })
+    }
+
+    pub fn from_config(cfg: &crate::config::AppConfig) -> Result<Self> {
+        Ok(Self::new(&cfg.db_dsn)?.with_chunk_size(cfg.import_chunk_size))
+    }
+
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Runs the synchronous repository calls in `f` on tokio's blocking threads, so
+    /// diesel never stalls the async runtime. Calls queue here for one of the pool's
+    /// connections instead of each holding a blocking thread while they wait.
+    pub async fn blocking<R, F>(&self, f: F) -> Result<R>
+    where
+        R: Send + 'static,
+        F: FnOnce(&Self) -> Result<R> + Send + 'static,
+    {
+        let permit = Arc::clone(&self.permits).acquire_owned().await?;
+        let repo = self.clone();
+        tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            f(&repo)
+        })
+        .await?
+    }
 
     pub fn get_sites(&self, sites: Vec<&str>) -> Result<Vec<models::SitesConfig>> {
         use schema::sites_config;
@@ -41,42 +203,907 @@ impl Repository<diesel::MysqlConnection> {
             let res = sites_config::table
                 .filter(schema::sites_config::enabled.eq(true))
                 .select(models::SitesConfig::as_select())
-                .load(&mut conn)
-                .unwrap();
+                .load(&mut conn)?;
             return Ok(res);
         }
 
         let res = sites_config::table
             .filter(sites_config::site_name.eq_any(sites))
             .select(models::SitesConfig::as_select())
-            .load(&mut conn)
-            .unwrap();
+            .load(&mut conn)?;
         Ok(res)
     }
-}
 
-impl RepositoryOps for Repository<diesel::MysqlConnection> {
-    fn import_locations(
+    pub fn list_events(
         &self,
-        _site_name: &str,
-        locations: Vec<models::SitesLocation>,
-    ) -> Result<()> {
+        filter: &EventFilter,
+        page: Page,
+    ) -> Result<(Vec<models::Event>, i64)> {
+        use schema::events;
+
+        let mut conn = self.pool.get()?;
+
+        let total = filtered_events(filter).count().get_result(&mut conn)?;
+        let res = filtered_events(filter)
+            .order((events::datetime, events::id))
+            .limit(page.per_page)
+            .offset(page.offset())
+            .select(models::Event::as_select())
+            .load(&mut conn)?;
+        Ok((res, total))
+    }
+
+    /// Counts in the order of the keys.
+    pub fn list_event_counts(&self, filter: &EventFilter, by: CountBy) -> Result<Vec<EventCount>> {
+        use diesel::dsl::count_star;
+        use schema::events;
+
+        let mut conn = self.pool.get()?;
+
+        // diesel can't group a boxed query, so the filter goes in a subquery
+        let q = events::table.filter(events::id.eq_any(filtered_events(filter).select(events::id)));
+        let rows: Vec<(Option<String>, i64)> = match by {
+            CountBy::Site => q
+                .group_by(events::site)
+                .select((events::site.nullable(), count_star()))
+                .order(events::site)
+                .load(&mut conn)?,
+            CountBy::Division => q
+                .group_by(events::division)
+                .select((events::division, count_star()))
+                .order(events::division)
+                .load(&mut conn)?,
+            CountBy::AgeGroup => q
+                .group_by(events::age_group)
+                .select((events::age_group, count_star()))
+                .order(events::age_group)
+                .load(&mut conn)?,
+            CountBy::Location => q
+                .group_by(events::location_id)
+                .select((events::location_id, count_star()))
+                .order(events::location_id)
+                .load::<(Option<i32>, i64)>(&mut conn)?
+                .into_iter()
+                .map(|(id, n)| (id.map(|id| id.to_string()), n))
+                .collect(),
+            CountBy::Surface => q
+                .group_by(events::surface_id)
+                .select((events::surface_id, count_star()))
+                .order(events::surface_id)
+                .load::<(i32, i64)>(&mut conn)?
+                .into_iter()
+                .map(|(id, n)| (Some(id.to_string()), n))
+                .collect(),
+        };
+        Ok(rows
+            .into_iter()
+            .map(|(key, events)| EventCount { key, events })
+            .collect())
+    }
+
+    pub fn get_site(&self, site_name: &str) -> Result<Option<models::SitesConfig>> {
+        use schema::sites_config;
+
         let mut conn = self.pool.get()?;
 
-        diesel::insert_into(schema::sites_locations::table)
-            .values(&locations)
-            .on_conflict_do_nothing()
+        let res = sites_config::table
+            .filter(sites_config::site_name.eq(site_name))
+            .select(models::SitesConfig::as_select())
+            .first(&mut conn)
+            .optional()?;
+        Ok(res)
+    }
+
+    pub fn insert_site(&self, site: &models::NewSitesConfig) -> Result<()> {
+        let mut conn = self.pool.get()?;
+
+        diesel::insert_into(schema::sites_config::table)
+            .values(site)
             .execute(&mut conn)?;
         Ok(())
     }
 
-    fn import_games(&self, games: Vec<models::InsertEvent>) -> Result<()> {
+    /// Returns false when there is no such site.
+    pub fn update_site(
+        &self,
+        site_name: &str,
+        changes: &models::SitesConfigChanges,
+    ) -> Result<bool> {
+        use schema::sites_config;
+
         let mut conn = self.pool.get()?;
-        diesel::insert_into(schema::events::table)
-            .values(&games)
-            .on_conflict_do_nothing()
+
+        let n = diesel::update(sites_config::table.filter(sites_config::site_name.eq(site_name)))
+            .set((changes, sites_config::updated_at.eq(diesel::dsl::now)))
             .execute(&mut conn)?;
+        Ok(n > 0)
+    }
 
+    /// The latest runs of `site`, newest first.
+    pub fn recent_scrape_runs(&self, site: &str, limit: i64) -> Result<Vec<models::ScrapeRun>> {
+        use schema::scrape_runs;
+
+        let mut conn = self.pool.get()?;
+
+        let res = scrape_runs::table
+            .filter(scrape_runs::site.eq(site))
+            .order(scrape_runs::started_at.desc())
+            .limit(limit)
+            .select(models::ScrapeRun::as_select())
+            .load(&mut conn)?;
+        Ok(res)
+    }
+
+    pub fn insert_scrape_run(&self, run: &models::NewScrapeRun) -> Result<()> {
+        let mut conn = self.pool.get()?;
+
+        diesel::insert_into(schema::scrape_runs::table)
+            .values(run)
+            .execute(&mut conn)?;
         Ok(())
     }
+
+    /// Events with the mapped venue and surface, for calendar feeds. Returns at most
+    /// `limit` events in start order.
+    pub fn list_events_with_venue(
+        &self,
+        filter: &EventFilter,
+        limit: i64,
+    ) -> Result<Vec<models::EventWithVenue>> {
+        use schema::{events, locations, surfaces};
+
+        let mut conn = self.pool.get()?;
+
+        let rows: Vec<(models::Event, Option<VenueColumns>, Option<String>)> =
+            filtered_events(filter)
+                .left_join(locations::table.on(events::location_id.eq(locations::id.nullable())))
+                .left_join(surfaces::table.on(events::surface_id.eq(surfaces::id)))
+                .order((events::datetime, events::id))
+                .limit(limit)
+                .select((
+                    models::Event::as_select(),
+                    (locations::name, locations::address1, locations::city).nullable(),
+                    surfaces::name.nullable(),
+                ))
+                .load(&mut conn)?;
+
+        let res = rows
+            .into_iter()
+            .map(|(event, venue, surface_name)| {
+                let (venue_name, venue_address, venue_city) = match venue {
+                    Some((name, address, city)) => (Some(name), address, city),
+                    _ => (None, None, None),
+                };
+                models::EventWithVenue {
+                    event,
+                    venue_name,
+                    venue_address,
+                    venue_city,
+                    surface_name,
+                }
+            })
+            .collect();
+        Ok(res)
+    }
+
+    pub fn list_sites(&self) -> Result<Vec<models::SitesConfig>> {
+        use schema::sites_config;
+
+        let mut conn = self.pool.get()?;
+
+        let res = sites_config::table
+            .order(sites_config::site_name)
+            .select(models::SitesConfig::as_select())
+            .load(&mut conn)?;
+        Ok(res)
+    }
+
+    pub fn list_locations(&self, page: Page) -> Result<(Vec<models::LocationRow>, i64)> {
+        use schema::locations;
+
+        let mut conn = self.pool.get()?;
+
+        let total = locations::table.count().get_result(&mut conn)?;
+        let res = locations::table
+            .order(locations::id)
+            .limit(page.per_page)
+            .offset(page.offset())
+            .select(models::LocationRow::as_select())
+            .load(&mut conn)?;
+        Ok((res, total))
+    }
+
+    pub fn list_surfaces(
+        &self,
+        location_id: Option<i32>,
+        page: Page,
+    ) -> Result<(Vec<models::SurfaceRow>, i64)> {
+        use schema::surfaces;
+
+        let mut conn = self.pool.get()?;
+
+        let query = || {
+            let mut q = surfaces::table.into_boxed();
+            if let Some(id) = location_id {
+                q = q.filter(surfaces::location_id.eq(id));
+            }
+            q
+        };
+        let total = query().count().get_result(&mut conn)?;
+        let res = query()
+            .order(surfaces::id)
+            .limit(page.per_page)
+            .offset(page.offset())
+            .select(models::SurfaceRow::as_select())
+            .load(&mut conn)?;
+        Ok((res, total))
+    }
+
+    pub fn list_sites_locations(
+        &self,
+        site: Option<&str>,
+        page: Page,
+    ) -> Result<(Vec<models::SitesLocationRow>, i64)> {
+        use schema::sites_locations;
+
+        let mut conn = self.pool.get()?;
+
+        let query = || {
+            let mut q = sites_locations::table.into_boxed();
+            if let Some(site) = site {
+                q = q.filter(sites_locations::site.eq(site));
+            }
+            q
+        };
+        let total = query().count().get_result(&mut conn)?;
+        let res = query()
+            .order((sites_locations::site, sites_locations::location))
+            .limit(page.per_page)
+            .offset(page.offset())
+            .select(models::SitesLocationRow::as_select())
+            .load(&mut conn)?;
+        Ok((res, total))
+    }
+
+    pub fn get_events_between(
+        &self,
+        from: chrono::NaiveDateTime,
+        to: chrono::NaiveDateTime,
+    ) -> Result<Vec<models::Event>> {
+        use schema::events;
+
+        let mut conn = self.pool.get()?;
+
+        let res = events::table
+            .filter(events::datetime.between(from, to))
+            .filter(events::deleted_at.is_null())
+            .order(events::datetime)
+            .select(models::Event::as_select())
+            .load(&mut conn)?;
+        Ok(res)
+    }
+
+    /// Events of one site, for comparing with a new scrape of the same days.
+    pub fn get_site_events_between(
+        &self,
+        site: &str,
+        from: chrono::NaiveDateTime,
+        to: chrono::NaiveDateTime,
+    ) -> Result<Vec<models::Event>> {
+        use schema::events;
+
+        let mut conn = self.pool.get()?;
+
+        let res = events::table
+            .filter(events::site.eq(site))
+            .filter(events::datetime.between(from, to))
+            .filter(events::deleted_at.is_null())
+            .order(events::datetime)
+            .select(models::Event::as_select())
+            .load(&mut conn)?;
+        Ok(res)
+    }
+
+    pub fn link_duplicates(&self, groups: &[dedup::DuplicateGroup]) -> Result<()> {
+        use schema::events;
+
+        let mut conn = self.pool.get()?;
+
+        conn.transaction(|conn| {
+            for g in groups {
+                diesel::update(events::table.find(g.canonical_id))
+                    .set(events::canonical_id.eq(None::<db::UnsignedBig>))
+                    .execute(conn)?;
+
+                if !g.merged.is_empty() {
+                    diesel::update(events::table.find(g.canonical_id))
+                        .set(&g.merged)
+                        .execute(conn)?;
+                }
+
+                diesel::update(events::table.filter(events::id.eq_any(&g.duplicate_ids)))
+                    .set(events::canonical_id.eq(g.canonical_id))
+                    .execute(conn)?;
+            }
+            diesel::QueryResult::Ok(())
+        })?;
+        Ok(())
+    }
+}
+
+// locations name, address1 and city
+type VenueColumns = (String, Option<String>, Option<String>);
+
+// LIKE ignores case with the MySQL collation and in SQLite, PostgreSQL needs ILIKE. The
+// pattern escapes its wildcards with LIKE_ESCAPE, see `contains_pattern`.
+#[cfg(not(feature = "postgres"))]
+macro_rules! like_nocase {
+    ($column:expr, $pattern:expr) => {
+        $column.like($pattern).escape(LIKE_ESCAPE)
+    };
+}
+#[cfg(feature = "postgres")]
+macro_rules! like_nocase {
+    ($column:expr, $pattern:expr) => {
+        $column.ilike($pattern).escape(LIKE_ESCAPE)
+    };
+}
+
+const LIKE_ESCAPE: char = '\\';
+
+/// LIKE pattern matching `s` anywhere, with the wildcards in `s` taken literally.
+fn contains_pattern(s: &str) -> String {
+    let mut pattern = String::with_capacity(s.len() + 2);
+    pattern.push('%');
+    for c in s.chars() {
+        if c == '%' || c == '_' || c == LIKE_ESCAPE {
+            pattern.push(LIKE_ESCAPE);
+        }
+        pattern.push(c);
+    }
+    pattern.push('%');
+    pattern
+}
+
+fn filtered_events(filter: &EventFilter) -> schema::events::BoxedQuery<'_, db::Backend> {
+    use schema::events;
+
+    let mut q = events::table
+        .filter(events::deleted_at.is_null())
+        .into_boxed();
+
+    if let Some(site) = &filter.site {
+        q = q.filter(events::site.eq(site));
+    }
+    if let Some(from) = filter.from {
+        q = q.filter(events::datetime.ge(from));
+    }
+    if let Some(to) = filter.to {
+        q = q.filter(events::datetime.le(to));
+    }
+    if let Some(division) = &filter.division {
+        q = q.filter(like_nocase!(events::division, contains_pattern(division)));
+    }
+    if let Some(age_group) = &filter.age_group {
+        q = q.filter(events::age_group.eq(age_group));
+    }
+    if let Some(tier) = &filter.tier {
+        q = q.filter(events::tier.eq(tier));
+    }
+    if let Some(gender) = &filter.gender {
+        q = q.filter(events::gender.eq(gender));
+    }
+    if let Some(team) = &filter.team {
+        let pattern = contains_pattern(team);
+        q = q.filter(
+            like_nocase!(events::home_team, pattern.clone())
+                .or(like_nocase!(events::guest_team, pattern)),
+        );
+    }
+    if let Some(location_id) = filter.location_id {
+        q = q.filter(events::location_id.eq(location_id));
+    }
+    if let Some(surface_id) = filter.surface_id {
+        q = q.filter(events::surface_id.eq(surface_id));
+    }
+    if !filter.include_duplicates {
+        q = q.filter(events::canonical_id.is_null());
+    }
+    q
+}
+
+#[async_trait::async_trait]
+impl RepositoryOps for Repository<DbConnection> {
+    async fn import_locations(
+        &self,
+        site_name: &str,
+        locations: Vec<models::SitesLocation>,
+    ) -> Result<()> {
+        let n = locations.len();
+        self.blocking(move |repo| {
+            let _timer = METRICS
+                .db_import_duration
+                .with_label_values(&["locations"])
+                .start_timer();
+            let mut conn = repo.pool.get()?;
+            conn.transaction(|conn| {
+                for chunk in locations.chunks(repo.chunk_size) {
+                    db::insert_ignore!(schema::sites_locations::table, chunk, conn)?;
+                }
+                diesel::QueryResult::Ok(())
+            })?;
+            Ok(())
+        })
+        .await
+        .with_context(|| format!("failed to import {} locations of {}", n, site_name))
+    }
+
+    async fn import_games(&self, games: Vec<models::InsertEvent>) -> Result<()> {
+        let n = games.len();
+        self.blocking(move |repo| {
+            let _timer = METRICS
+                .db_import_duration
+                .with_label_values(&["games"])
+                .start_timer();
+            let mut conn = repo.pool.get()?;
+            conn.transaction(|conn| {
+                for chunk in games.chunks(repo.chunk_size) {
+                    db::insert_ignore!(schema::events::table, chunk, conn)?;
+                }
+                diesel::QueryResult::Ok(())
+            })?;
+            Ok(())
+        })
+        .await
+        .with_context(|| format!("failed to import {} games", n))
+    }
+
+    async fn sync_games(
+        &self,
+        site_name: &str,
+        from: chrono::NaiveDateTime,
+        to: chrono::NaiveDateTime,
+        games: Vec<models::InsertEvent>,
+        max_cancelled_share: f64,
+    ) -> Result<Vec<ScheduleChange>> {
+        use schema::events;
+
+        let site = site_name.to_string();
+        self.blocking(move |repo| {
+            let _timer = METRICS
+                .db_import_duration
+                .with_label_values(&["games"])
+                .start_timer();
+            let (window, outside): (Vec<_>, Vec<_>) = games
+                .into_iter()
+                .partition(|g| g.datetime >= from && g.datetime < to);
+
+            let mut conn = repo.pool.get()?;
+            conn.transaction(|conn| {
+                let existing = events::table
+                    .filter(events::site.eq(&site))
+                    .filter(events::datetime.ge(from))
+                    .filter(events::datetime.lt(to))
+                    .order((events::datetime, events::id))
+                    .select(models::Event::as_select())
+                    .load(conn)?;
+                let plan = schedule_diff::plan_sync(&existing, &window);
+                plan.check_cancellations(max_cancelled_share)?;
+
+                for &(id, g) in &plan.update {
+                    diesel::update(events::table.find(id))
+                        .set(&models::EventChanges::from(&window[g]))
+                        .execute(conn)?;
+                }
+                for ids in plan.remove.chunks(repo.chunk_size) {
+                    diesel::update(events::table.filter(events::id.eq_any(ids)))
+                        .set(events::deleted_at.eq(diesel::dsl::now))
+                        .execute(conn)?;
+                }
+                // plan.insert is in the order of the scrape
+                let new: Vec<models::InsertEvent> = window
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(g, _)| plan.insert.binary_search(g).is_ok())
+                    .map(|(_, game)| game)
+                    .collect();
+                for chunk in new.chunks(repo.chunk_size) {
+                    db::insert_ignore!(events::table, chunk, conn)?;
+                }
+                for chunk in outside.chunks(repo.chunk_size) {
+                    db::insert_ignore!(events::table, chunk, conn)?;
+                }
+                Ok(plan.changes)
+            })
+        })
+        .await
+        .with_context(|| format!("failed to sync the games of {}", site_name))
+    }
+}
+
+#[async_trait::async_trait]
+impl EventQueries for Repository<DbConnection> {
+    async fn find_events(
+        &self,
+        filter: EventFilter,
+        page: Page,
+    ) -> Result<(Vec<models::Event>, i64)> {
+        self.blocking(move |repo| repo.list_events(&filter, page))
+            .await
+    }
+
+    async fn find_events_with_venue(
+        &self,
+        filter: EventFilter,
+        limit: i64,
+    ) -> Result<Vec<models::EventWithVenue>> {
+        self.blocking(move |repo| repo.list_events_with_venue(&filter, limit))
+            .await
+    }
+
+    async fn count_events(&self, filter: EventFilter, by: CountBy) -> Result<Vec<EventCount>> {
+        self.blocking(move |repo| repo.list_event_counts(&filter, by))
+            .await
+    }
+
+    async fn site_events_between(
+        &self,
+        site: &str,
+        from: chrono::NaiveDateTime,
+        to: chrono::NaiveDateTime,
+    ) -> Result<Vec<models::Event>> {
+        let site = site.to_string();
+        self.blocking(move |repo| repo.get_site_events_between(&site, from, to))
+            .await
+    }
+}
+
+/// Discards everything, for running the scraper without a database, e.g. `scrape preview`.
+#[derive(Debug, Default)]
+pub struct NoopRepository;
+
+#[async_trait::async_trait]
+impl RepositoryOps for NoopRepository {
+    async fn import_locations(
+        &self,
+        _site_name: &str,
+        _locations: Vec<models::SitesLocation>,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    async fn import_games(&self, _games: Vec<models::InsertEvent>) -> Result<()> {
+        Ok(())
+    }
+
+    async fn sync_games(
+        &self,
+        _site_name: &str,
+        _from: chrono::NaiveDateTime,
+        _to: chrono::NaiveDateTime,
+        _games: Vec<models::InsertEvent>,
+        _max_cancelled_share: f64,
+    ) -> Result<Vec<ScheduleChange>> {
+        Ok(Vec::new())
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod test {
+    use super::*;
+    use chrono::Datelike;
+
+    fn test_repo(name: &str) -> Repository<DbConnection> {
+        let path = std::env::temp_dir().join(format!(
+            "calscraper-repo-{}-{}.db",
+            std::process::id(),
+            name
+        ));
+        let _ = std::fs::remove_file(&path);
+        let repo = Repository::new(path.to_str().unwrap()).unwrap();
+        db::run_migrations(&mut repo.pool.get().unwrap()).unwrap();
+        repo
+    }
+
+    fn game(day: u32, home: &str) -> models::InsertEvent {
+        models::InsertEvent {
+            site: "whitby".into(),
+            datetime: chrono::NaiveDate::from_ymd_opt(2025, 11, day)
+                .unwrap()
+                .and_hms_opt(19, 0, 0)
+                .unwrap(),
+            home_team: home.into(),
+            guest_team: "Ajax".into(),
+            location: None,
+            division: None,
+            location_id: Some(0),
+            surface_id: 0,
+            age_group: None,
+            tier: None,
+            gender: None,
+            season_type: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_import_games_in_chunks() {
+        let repo = test_repo("import-games").with_chunk_size(2);
+        let games = vec![
+            game(1, "Whitby"),
+            game(2, "Whitby"),
+            game(3, "Whitby"),
+            // already in the first chunk
+            game(1, "Whitby"),
+            game(4, "Whitby"),
+        ];
+        repo.import_games(games).await.unwrap();
+        repo.import_games(vec![game(5, "Whitby")]).await.unwrap();
+
+        let filter = EventFilter {
+            site: Some("whitby".into()),
+            ..Default::default()
+        };
+        let page = Page {
+            page: 1,
+            per_page: 10,
+        };
+        let (events, total) = repo.list_events(&filter, page).unwrap();
+        assert_eq!(5, total);
+        assert_eq!(
+            vec![1, 2, 3, 4, 5],
+            events.iter().map(|e| e.datetime.day()).collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_import_rolls_back_earlier_chunks() {
+        let repo = test_repo("import-rollback").with_chunk_size(2);
+        repo.import_games(vec![game(1, "Whitby")]).await.unwrap();
+        // INSERT OR IGNORE skips constraint violations, a trigger still aborts the insert
+        diesel::sql_query(
+            "CREATE TRIGGER fail_import BEFORE INSERT ON events WHEN NEW.home_team = 'Boom' \
+             BEGIN SELECT RAISE(ABORT, 'boom'); END",
+        )
+        .execute(&mut repo.pool.get().unwrap())
+        .unwrap();
+
+        let games = vec![
+            game(2, "Whitby"),
+            game(3, "Whitby"),
+            game(4, "Whitby"),
+            game(5, "Boom"),
+        ];
+        let err = repo.import_games(games).await.unwrap_err();
+        assert!(format!("{:#}", err).contains("boom"), "{:#}", err);
+
+        let filter = EventFilter {
+            site: Some("whitby".into()),
+            ..Default::default()
+        };
+        let page = Page {
+            page: 1,
+            per_page: 10,
+        };
+        let (events, total) = repo.list_events(&filter, page).unwrap();
+        assert_eq!(1, total);
+        assert_eq!(1, events[0].datetime.day());
+    }
+
+    #[tokio::test]
+    async fn test_sync_games() {
+        let repo = test_repo("sync-games");
+        repo.import_games(vec![
+            game(1, "Whitby"),
+            game(2, "Whitby"),
+            game(3, "Whitby"),
+        ])
+        .await
+        .unwrap();
+
+        let mut moved = game(2, "Whitby");
+        moved.datetime += chrono::Duration::hours(1);
+        let mut next_month = game(1, "Whitby");
+        next_month.datetime = next_month.datetime.with_month(12).unwrap();
+        let (from, to) = crate::site_scraper::scraped_window(game(1, "").datetime.date());
+        let games = vec![game(1, "Whitby"), moved, game(5, "Whitby"), next_month];
+
+        let changes = repo
+            .sync_games("whitby", from, to, games, DEFAULT_MAX_CANCELLED_SHARE)
+            .await
+            .unwrap();
+        assert_eq!(
+            vec![
+                (schedule_diff::ChangeKind::Updated, 2),
+                (schedule_diff::ChangeKind::Inserted, 5),
+                (schedule_diff::ChangeKind::Cancelled, 3),
+            ],
+            changes
+                .iter()
+                .map(|c| (c.kind, c.datetime.day()))
+                .collect::<Vec<_>>()
+        );
+
+        let filter = EventFilter {
+            site: Some("whitby".into()),
+            ..Default::default()
+        };
+        let page = Page {
+            page: 1,
+            per_page: 10,
+        };
+        let (events, _) = repo.list_events(&filter, page).unwrap();
+        assert_eq!(
+            vec![(11, 1), (11, 2), (11, 5), (12, 1)],
+            events
+                .iter()
+                .map(|e| (e.datetime.month(), e.datetime.day()))
+                .collect::<Vec<_>>()
+        );
+
+        // a scrape that lost the month cancels nothing
+        assert!(
+            repo.sync_games("whitby", from, to, Vec::new(), DEFAULT_MAX_CANCELLED_SHARE)
+                .await
+                .is_err()
+        );
+        assert_eq!(4, repo.list_events(&filter, page).unwrap().1);
+    }
+
+    #[tokio::test]
+    async fn test_sync_relisted_game_in_other_case() {
+        let repo = test_repo("sync-case");
+        let (from, to) = crate::site_scraper::scraped_window(game(1, "").datetime.date());
+        repo.import_games(vec![game(1, "Whitby")]).await.unwrap();
+        // cancelled, then back on the schedule spelled differently and listed twice
+        repo.sync_games("whitby", from, to, Vec::new(), 1.0)
+            .await
+            .unwrap();
+        let games = vec![game(1, "WHITBY"), game(1, "whitby"), game(2, "Whitby")];
+        let changes = repo
+            .sync_games("whitby", from, to, games, DEFAULT_MAX_CANCELLED_SHARE)
+            .await
+            .unwrap();
+        assert_eq!(2, changes.len());
+
+        let mut conn = repo.pool.get().unwrap();
+        let rows: Vec<(String, Option<chrono::NaiveDateTime>)> = schema::events::table
+            .order(schema::events::datetime)
+            .select((schema::events::home_team, schema::events::deleted_at))
+            .load(&mut conn)
+            .unwrap();
+        assert_eq!(
+            vec![("WHITBY".to_string(), None), ("Whitby".to_string(), None)],
+            rows
+        );
+    }
+
+    #[tokio::test]
+    async fn test_repeated_scrape_reports_changes_once() {
+        use schedule_diff::ChangeKind;
+
+        let repo = test_repo("repeated-scrape");
+        repo.import_games(vec![
+            game(1, "Whitby"),
+            game(2, "Whitby"),
+            game(3, "Whitby"),
+        ])
+        .await
+        .unwrap();
+        // day 2 moved by an hour, day 3 is gone from the scrape
+        let scrape = || {
+            let mut moved = game(2, "Whitby");
+            moved.datetime += chrono::Duration::hours(1);
+            vec![game(1, "Whitby"), moved, game(4, "Whitby")]
+        };
+        let window = crate::site_scraper::scraped_window(game(1, "").datetime.date());
+        let kinds =
+            |changes: Vec<ScheduleChange>| changes.iter().map(|c| c.kind).collect::<Vec<_>>();
+
+        for expected in [vec![ChangeKind::Updated, ChangeKind::Inserted], vec![]] {
+            let changes = schedule_diff::import_changes(&repo, "whitby", window, &scrape()).await;
+            repo.import_games(scrape()).await.unwrap();
+            assert_eq!(expected, kinds(changes));
+        }
+
+        let repo = test_repo("repeated-sync");
+        repo.import_games(vec![
+            game(1, "Whitby"),
+            game(2, "Whitby"),
+            game(3, "Whitby"),
+        ])
+        .await
+        .unwrap();
+        for expected in [
+            vec![
+                ChangeKind::Updated,
+                ChangeKind::Inserted,
+                ChangeKind::Cancelled,
+            ],
+            vec![],
+        ] {
+            let changes = repo
+                .sync_games("whitby", window.0, window.1, scrape(), 1.0)
+                .await
+                .unwrap();
+            assert_eq!(expected, kinds(changes));
+        }
+    }
+
+    #[test]
+    fn test_count_events() {
+        let repo = test_repo("count-events");
+        let mut u13 = game(2, "Oshawa");
+        u13.division = Some("U13 A".into());
+        u13.location_id = Some(42);
+        let mut other_site = game(3, "Ajax");
+        other_site.site = "ajax".into();
+        let mut conn = repo.pool.get().unwrap();
+        db::insert_ignore!(
+            schema::events::table,
+            &[game(1, "Whitby"), u13, other_site],
+            &mut conn
+        )
+        .unwrap();
+
+        let count = |filter: &EventFilter, by| {
+            repo.list_event_counts(filter, by)
+                .unwrap()
+                .into_iter()
+                .map(|c| (c.key, c.events))
+                .collect::<Vec<_>>()
+        };
+        let all = EventFilter::default();
+        assert_eq!(
+            vec![(Some("ajax".into()), 1), (Some("whitby".into()), 2)],
+            count(&all, CountBy::Site)
+        );
+        assert_eq!(
+            vec![(Some("0".into()), 2), (Some("42".into()), 1)],
+            count(&all, CountBy::Location)
+        );
+
+        let whitby = EventFilter {
+            site: Some("whitby".into()),
+            division: Some("u13".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            vec![(Some("U13 A".into()), 1)],
+            count(&whitby, CountBy::Division)
+        );
+    }
+
+    #[test]
+    fn test_team_filter_takes_wildcards_literally() {
+        let repo = test_repo("like-escape");
+        let mut conn = repo.pool.get().unwrap();
+        db::insert_ignore!(
+            schema::events::table,
+            &[
+                game(1, "Whitby_A"),
+                game(2, "WhitbyXA"),
+                game(3, "100% Hockey"),
+                game(4, "Back\\Check"),
+            ],
+            &mut conn
+        )
+        .unwrap();
+
+        let teams = |team: &str| {
+            let filter = EventFilter {
+                team: Some(team.into()),
+                ..Default::default()
+            };
+            let page = Page {
+                page: 1,
+                per_page: 10,
+            };
+            let (events, _) = repo.list_events(&filter, page).unwrap();
+            events.into_iter().map(|e| e.home_team).collect::<Vec<_>>()
+        };
+        assert_eq!(vec!["Whitby_A"], teams("y_a"));
+        assert_eq!(vec!["100% Hockey"], teams("%"));
+        assert_eq!(vec!["Back\\Check"], teams("k\\c"));
+        assert_eq!(4, teams("").len());
+    }
 }
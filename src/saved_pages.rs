@@ -0,0 +1,146 @@
+// Pages saved to disk for scraping without HTTP, e.g. to reproduce a parser regression.
+// Each page is stored under a file name derived from its url, see `saved_page_name`, so a
+// directory can hold a site's schedule pages together with the venue pages they link to.
+
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone)]
+pub struct SavedPages {
+    dir: PathBuf,
+}
+
+/// File name for `url`: its `url_key` plus ".html". e.g.
+/// "https://www.whitbyhockey.com/Calendar/?Month=11&Year=2025" is saved as
+/// "www.whitbyhockey.com_Calendar_Month_11_Year_2025.html", a name that can be given to a
+/// page saved by hand.
+pub fn saved_page_name(url: &str) -> String {
+    format!("{}.html", url_key(url))
+}
+
+/// Longest readable part of a key, well below the 255 bytes file systems allow for a name.
+const MAX_KEY_LEN: usize = 150;
+
+/// `url` without its scheme, with every run of characters other than letters, digits,
+/// '.' and '-' replaced by '_'. A longer key than MAX_KEY_LEN is cut and made unique as
+/// in `unique_url_key`.
+pub fn url_key(url: &str) -> String {
+    let key = readable_key(url);
+    if key.len() > MAX_KEY_LEN {
+        unique_url_key(url)
+    } else {
+        key
+    }
+}
+
+/// `url_key` followed by the start of the SHA-256 of the whole url. The readable part is
+/// lossy, "/a?b=1" and "/a/b/1" both flatten to "a_b_1", the hash keeps such urls apart.
+pub fn unique_url_key(url: &str) -> String {
+    let mut key = readable_key(url);
+    // only ASCII is kept, so any length is a char boundary
+    key.truncate(MAX_KEY_LEN);
+    key.push('_');
+    for b in &Sha256::digest(url.as_bytes())[..6] {
+        key.push_str(&format!("{:02x}", b));
+    }
+    key
+}
+
+fn readable_key(url: &str) -> String {
+    let url = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .unwrap_or(url);
+
+    let mut name = String::with_capacity(url.len());
+    for c in url.chars() {
+        if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+            name.push(c);
+        } else if !name.ends_with('_') {
+            name.push('_');
+        }
+    }
+    name.trim_matches('_').to_string()
+}
+
+impl SavedPages {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        SavedPages { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn path(&self, url: &str) -> PathBuf {
+        self.dir.join(saved_page_name(url))
+    }
+
+    /// The saved page for `url`, or None when it was not saved.
+    pub fn load(&self, url: &str) -> Result<Option<String>> {
+        let path = self.path(url);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = std::fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        Ok(Some(contents))
+    }
+
+    pub fn save(&self, url: &str, contents: &str) -> Result<PathBuf> {
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.path(url);
+        std::fs::write(&path, contents)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_saved_page_name() {
+        assert_eq!(
+            "www.whitbyhockey.com_Calendar_Month_11_Year_2025.html",
+            saved_page_name("https://www.whitbyhockey.com/Calendar/?Month=11&Year=2025")
+        );
+        assert_eq!(
+            "www.theonedb.com_Venue_Map_10566_day_19_month_12.html",
+            saved_page_name("http://www.theonedb.com/Venue/Map/10566?day=19&month=12")
+        );
+
+        let long = format!("https://example.com/Venue/?ids={}", "1,".repeat(200));
+        let name = saved_page_name(&long);
+        assert_eq!(MAX_KEY_LEN + 13 + 5, name.len());
+        assert_ne!(name, saved_page_name(&format!("{}2", long)));
+    }
+
+    #[test]
+    fn test_unique_url_key() {
+        // the same readable part
+        assert_ne!(
+            unique_url_key("https://example.com/Venue/?id=1"),
+            unique_url_key("https://example.com/Venue/id/1")
+        );
+        assert!(
+            unique_url_key("https://example.com/Venue/?id=1")
+                .starts_with("example.com_Venue_id_1_")
+        );
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = std::env::temp_dir().join(format!("saved-pages-{}", std::process::id()));
+        let pages = SavedPages::new(&dir);
+        let url = "https://example.com/Venue/1/";
+
+        assert!(pages.load(url).unwrap().is_none());
+        pages.save(url, "<html></html>").unwrap();
+        assert_eq!(Some("<html></html>".into()), pages.load(url).unwrap());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,565 @@
+// Compares the games a scrape found with the events already stored for the site, so an
+// import can tell what changed. Games are matched on the teams and the day: a game that
+// keeps its teams and day but moves to another time or rink is an update.
+//
+// `plan_sync` goes further for a full scrape of a window: it pairs the games with the
+// stored rows so the import can rewrite the window to match the scrape. Only a sync
+// reports cancellations, since it marks the row deleted and so announces each one once;
+// an import that only adds rows would announce the same missing game on every run.
+
+use crate::db::UnsignedBig;
+use crate::dedup::{self, normalize_team};
+use crate::models::{Event, InsertEvent};
+use crate::repository::EventQueries;
+use anyhow::{Result, bail};
+use chrono::{NaiveDate, NaiveDateTime};
+use tracing::warn;
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Inserted,
+    Updated,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct ScheduleChange {
+    pub kind: ChangeKind,
+    pub site: String,
+    pub home_team: String,
+    pub guest_team: String,
+    pub datetime: NaiveDateTime,
+    pub location: Option<String>,
+    pub division: Option<String>,
+    /// time and rink before an update
+    pub previous_datetime: Option<NaiveDateTime>,
+    pub previous_location: Option<String>,
+}
+
+impl ScheduleChange {
+    fn from_incoming(kind: ChangeKind, e: &InsertEvent) -> Self {
+        Self {
+            kind,
+            site: e.site.clone(),
+            home_team: e.home_team.clone(),
+            guest_team: e.guest_team.clone(),
+            datetime: e.datetime,
+            location: e.location.clone(),
+            division: e.division.clone(),
+            previous_datetime: None,
+            previous_location: None,
+        }
+    }
+
+    fn from_existing(kind: ChangeKind, e: &Event) -> Self {
+        Self {
+            kind,
+            site: e.site.clone(),
+            home_team: e.home_team.clone(),
+            guest_team: e.guest_team.clone(),
+            datetime: e.datetime,
+            location: e.location.clone(),
+            division: e.division.clone(),
+            previous_datetime: None,
+            previous_location: None,
+        }
+    }
+
+    /// One line for people, e.g. in an email.
+    pub fn describe(&self) -> String {
+        let game = format!(
+            "{} vs {}, {} at {}",
+            self.home_team,
+            self.guest_team,
+            self.datetime.format("%a %b %-d %H:%M"),
+            self.location.as_deref().unwrap_or("unknown rink")
+        );
+        match self.kind {
+            ChangeKind::Inserted => format!("New game: {}", game),
+            ChangeKind::Cancelled => format!("Cancelled: {}", game),
+            ChangeKind::Updated => format!(
+                "Changed: {} (was {} at {})",
+                game,
+                self.previous_datetime
+                    .map(|d| d.format("%a %b %-d %H:%M").to_string())
+                    .unwrap_or_default(),
+                self.previous_location.as_deref().unwrap_or("unknown rink")
+            ),
+        }
+    }
+}
+
+type Key = (String, String, NaiveDate);
+
+fn key(home: &str, guest: &str, datetime: NaiveDateTime) -> Key {
+    (normalize_team(home), normalize_team(guest), datetime.date())
+}
+
+fn normalize_location(location: &Option<String>) -> String {
+    location
+        .as_deref()
+        .map(dedup::normalize_location)
+        .unwrap_or_default()
+}
+
+fn same_slot(existing: &Event, incoming: &InsertEvent) -> bool {
+    existing.datetime == incoming.datetime
+        && normalize_location(&existing.location) == normalize_location(&incoming.location)
+}
+
+/// Diffs `incoming`, the games of one scrape of a site, with `existing`, the site's
+/// stored events over the same days, into new and updated games. The import only adds
+/// rows, so a game that moved keeps its old row next to the new one; any stored row that
+/// still matches the scrape means the game is unchanged.
+pub fn diff(existing: &[Event], incoming: &[InsertEvent]) -> Vec<ScheduleChange> {
+    let existing_keys: Vec<Key> = existing
+        .iter()
+        .map(|e| key(&e.home_team, &e.guest_team, e.datetime))
+        .collect();
+    let mut seen = vec![false; existing.len()];
+    let mut changes = Vec::new();
+
+    for game in incoming {
+        let k = key(&game.home_team, &game.guest_team, game.datetime);
+        let same_key: Vec<usize> = (0..existing.len())
+            .filter(|&i| existing_keys[i] == k)
+            .collect();
+
+        if same_key.iter().any(|&i| same_slot(&existing[i], game)) {
+            for i in same_key {
+                seen[i] = true;
+            }
+            continue;
+        }
+        match same_key.iter().find(|&&i| !seen[i]) {
+            Some(&i) => {
+                seen[i] = true;
+                let mut change = ScheduleChange::from_incoming(ChangeKind::Updated, game);
+                change.previous_datetime = Some(existing[i].datetime);
+                change.previous_location = existing[i].location.clone();
+                changes.push(change);
+            }
+            None => changes.push(ScheduleChange::from_incoming(ChangeKind::Inserted, game)),
+        }
+    }
+    changes
+}
+
+/// What importing `incoming` changes in the site's schedule, see `diff`. `window` is
+/// what the scrape covered, from its start up to its end, e.g.
+/// `site_scraper::scraped_window`; the stored events are read over it and any days
+/// around it the games fall on. Changes are only notices, so a failed read is logged and
+/// gives none.
+pub async fn import_changes<R>(
+    repo: &R,
+    site: &str,
+    window: (NaiveDateTime, NaiveDateTime),
+    incoming: &[InsertEvent],
+) -> Vec<ScheduleChange>
+where
+    R: EventQueries + ?Sized,
+{
+    let (Some(first), Some(last)) = (
+        incoming.iter().map(|e| e.datetime).min(),
+        incoming.iter().map(|e| e.datetime).max(),
+    ) else {
+        return Vec::new();
+    };
+    let from = window.0.min(first.date().and_hms_opt(0, 0, 0).unwrap());
+    let to =
+        (window.1 - chrono::Duration::seconds(1)).max(last.date().and_hms_opt(23, 59, 59).unwrap());
+    match repo.site_events_between(site, from, to).await {
+        Ok(existing) => diff(&existing, incoming),
+        Err(e) => {
+            warn!("failed to load events of {}: {:#}", site, e);
+            Vec::new()
+        }
+    }
+}
+
+/// How a sync rewrites a site's stored events over one window, see `plan_sync`.
+#[derive(Debug, Default)]
+pub struct SyncPlan {
+    /// indexes of the incoming games without a stored row
+    pub insert: Vec<usize>,
+    /// stored rows to overwrite with an incoming game, which also restores removed rows
+    pub update: Vec<(UnsignedBig, usize)>,
+    /// stored rows to mark deleted
+    pub remove: Vec<UnsignedBig>,
+    pub changes: Vec<ScheduleChange>,
+    /// stored rows in the window that were not deleted before the sync
+    pub live: usize,
+}
+
+impl SyncPlan {
+    pub fn cancelled(&self) -> usize {
+        self.changes
+            .iter()
+            .filter(|c| c.kind == ChangeKind::Cancelled)
+            .count()
+    }
+
+    /// Fails when the sync would cancel more than `max_share` of the stored games, which
+    /// rather means the scrape missed part of the schedule. A few cancellations are
+    /// always allowed, so a site with a handful of games can still lose them.
+    pub fn check_cancellations(&self, max_share: f64) -> Result<()> {
+        let cancelled = self.cancelled();
+        if cancelled > MIN_CANCELLATIONS_CHECKED && cancelled as f64 > self.live as f64 * max_share
+        {
+            bail!(
+                "the scrape would cancel {} of {} stored games, it looks truncated",
+                cancelled,
+                self.live
+            );
+        }
+        Ok(())
+    }
+}
+
+const MIN_CANCELLATIONS_CHECKED: usize = 2;
+
+fn same_fields(existing: &Event, incoming: &InsertEvent) -> bool {
+    existing.location == incoming.location
+        && existing.division == incoming.division
+        && existing.age_group == incoming.age_group
+        && existing.tier == incoming.tier
+        && existing.gender == incoming.gender
+        && existing.season_type == incoming.season_type
+}
+
+type RowKey = (NaiveDateTime, String, String);
+
+// the columns of the unique key uq_events_game as its collation compares them, ignoring
+// case and trailing spaces
+fn row_key(datetime: NaiveDateTime, home: &str, guest: &str) -> RowKey {
+    let norm = |s: &str| s.trim_end().to_lowercase();
+    (datetime, norm(home), norm(guest))
+}
+
+/// Plans a sync of `existing`, every stored event of a site over a window including the
+/// deleted ones, to `incoming`, a full scrape of the site over that window.
+///
+/// A game keeps the row with its exact time and teams, compared like the unique key does,
+/// which allows such a row only once, so it is updated in place and restored if it was deleted. Otherwise a
+/// game takes a live row of the same teams and day as in `diff`, or is inserted. The live
+/// rows left over are removed: as cancellations, or silently when the game is still
+/// scraped and they are the old rows of earlier moves.
+pub fn plan_sync(existing: &[Event], incoming: &[InsertEvent]) -> SyncPlan {
+    let existing_keys: Vec<Key> = existing
+        .iter()
+        .map(|e| key(&e.home_team, &e.guest_team, e.datetime))
+        .collect();
+    let existing_rows: Vec<RowKey> = existing
+        .iter()
+        .map(|e| row_key(e.datetime, &e.home_team, &e.guest_team))
+        .collect();
+    let mut seen = vec![false; existing.len()];
+    let mut plan = SyncPlan {
+        live: existing.iter().filter(|e| e.deleted_at.is_none()).count(),
+        ..Default::default()
+    };
+
+    // a scrape can list a game twice, e.g. on the pages of both teams
+    let mut games: Vec<usize> = Vec::new();
+    let mut game_rows: Vec<RowKey> = Vec::new();
+    for (i, g) in incoming.iter().enumerate() {
+        let row = row_key(g.datetime, &g.home_team, &g.guest_team);
+        if !game_rows.contains(&row) {
+            games.push(i);
+            game_rows.push(row);
+        }
+    }
+
+    let mut unmatched = Vec::new();
+    for (&g, row) in games.iter().zip(&game_rows) {
+        let game = &incoming[g];
+        let exact = existing_rows.iter().position(|r| r == row);
+        let Some(i) = exact else {
+            unmatched.push(g);
+            continue;
+        };
+        seen[i] = true;
+        let e = &existing[i];
+        if e.deleted_at.is_some() {
+            plan.update.push((e.id, g));
+            plan.changes
+                .push(ScheduleChange::from_incoming(ChangeKind::Inserted, game));
+        } else if !same_fields(e, game) {
+            plan.update.push((e.id, g));
+            if !same_slot(e, game) {
+                let mut change = ScheduleChange::from_incoming(ChangeKind::Updated, game);
+                change.previous_datetime = Some(e.datetime);
+                change.previous_location = e.location.clone();
+                plan.changes.push(change);
+            }
+        }
+    }
+
+    for g in unmatched {
+        let game = &incoming[g];
+        let k = key(&game.home_team, &game.guest_team, game.datetime);
+        let candidates: Vec<usize> = (0..existing.len())
+            .filter(|&i| !seen[i] && existing[i].deleted_at.is_none() && existing_keys[i] == k)
+            .collect();
+
+        // the same game with the team names spelled differently
+        if let Some(&i) = candidates.iter().find(|&&i| same_slot(&existing[i], game)) {
+            seen[i] = true;
+            continue;
+        }
+        match candidates.first() {
+            Some(&i) => {
+                seen[i] = true;
+                plan.update.push((existing[i].id, g));
+                let mut change = ScheduleChange::from_incoming(ChangeKind::Updated, game);
+                change.previous_datetime = Some(existing[i].datetime);
+                change.previous_location = existing[i].location.clone();
+                plan.changes.push(change);
+            }
+            None => {
+                plan.insert.push(g);
+                plan.changes
+                    .push(ScheduleChange::from_incoming(ChangeKind::Inserted, game));
+            }
+        }
+    }
+
+    let incoming_keys: Vec<Key> = incoming
+        .iter()
+        .map(|g| key(&g.home_team, &g.guest_team, g.datetime))
+        .collect();
+    for (i, e) in existing.iter().enumerate() {
+        if seen[i] || e.deleted_at.is_some() {
+            continue;
+        }
+        plan.remove.push(e.id);
+        if !incoming_keys.contains(&existing_keys[i]) {
+            plan.changes
+                .push(ScheduleChange::from_existing(ChangeKind::Cancelled, e));
+        }
+    }
+    plan
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn at(day: u32, hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 11, day)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    }
+
+    fn incoming(home: &str, guest: &str, datetime: NaiveDateTime, rink: &str) -> InsertEvent {
+        InsertEvent {
+            site: "whitby".into(),
+            datetime,
+            home_team: home.into(),
+            guest_team: guest.into(),
+            location: Some(rink.into()),
+            division: Some("U13 A".into()),
+            location_id: Some(0),
+            surface_id: 0,
+            age_group: None,
+            tier: None,
+            gender: None,
+            season_type: None,
+        }
+    }
+
+    fn stored(
+        id: UnsignedBig,
+        home: &str,
+        guest: &str,
+        datetime: NaiveDateTime,
+        rink: &str,
+    ) -> Event {
+        Event {
+            id,
+            site: "whitby".into(),
+            source_type: None,
+            datetime,
+            home_team: home.into(),
+            oid_home: None,
+            guest_team: guest.into(),
+            oid_guest: None,
+            location: Some(rink.into()),
+            division: Some("U13 A".into()),
+            location_id: Some(0),
+            surface_id: 0,
+            date_created: datetime,
+            age_group: None,
+            tier: None,
+            gender: None,
+            season_type: None,
+            canonical_id: None,
+            deleted_at: None,
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let existing = vec![
+            stored(1, "Whitby", "Ajax", at(1, 18), "Iroquois"),
+            stored(2, "Whitby", "Oshawa", at(2, 9), "Iroquois"),
+            stored(3, "Whitby", "Pickering", at(3, 17), "McKinney"),
+        ];
+        let games = vec![
+            // unchanged, the team name differs only in case
+            incoming("WHITBY", "Ajax", at(1, 18), "Iroquois"),
+            // moved to another time and rink on the same day
+            incoming("Whitby", "Oshawa", at(2, 11), "McKinney"),
+            incoming("Whitby", "Clarington", at(4, 12), "McKinney"),
+        ];
+
+        let changes = diff(&existing, &games);
+        assert_eq!(2, changes.len());
+
+        assert_eq!(ChangeKind::Updated, changes[0].kind);
+        assert_eq!(at(2, 11), changes[0].datetime);
+        assert_eq!(Some(at(2, 9)), changes[0].previous_datetime);
+        assert_eq!(Some("Iroquois".into()), changes[0].previous_location);
+
+        assert_eq!(ChangeKind::Inserted, changes[1].kind);
+        assert_eq!("Clarington", changes[1].guest_team);
+    }
+
+    #[test]
+    fn test_diff_ignores_rows_left_by_earlier_updates() {
+        // the old row of a game moved in an earlier import is still stored
+        let existing = vec![
+            stored(1, "Whitby", "Ajax", at(1, 18), "Iroquois"),
+            stored(2, "Whitby", "Ajax", at(1, 20), "McKinney"),
+        ];
+        let games = vec![incoming("Whitby", "Ajax", at(1, 20), "McKinney")];
+        assert!(diff(&existing, &games).is_empty());
+    }
+
+    #[test]
+    fn test_diff_reports_a_move_to_another_rink() {
+        let existing = vec![stored(1, "Whitby", "Ajax", at(1, 18), "Iroquois Rink A")];
+        let games = vec![incoming("Whitby", "Ajax", at(1, 18), "Iroquois Rink B")];
+        let changes = diff(&existing, &games);
+        assert_eq!(1, changes.len());
+        assert_eq!(ChangeKind::Updated, changes[0].kind);
+        assert_eq!(Some("Iroquois Rink A".into()), changes[0].previous_location);
+
+        let games = vec![incoming("Whitby", "Ajax", at(1, 18), "IROQUOIS - Rink A")];
+        assert!(diff(&existing, &games).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_import_changes() {
+        let mut repo = crate::repository::MockEventQueries::new();
+        repo.expect_site_events_between()
+            .withf(|site, from, to| {
+                site == "whitby" && *from == at(1, 0) && to.to_string() == "2025-11-30 23:59:59"
+            })
+            .times(1)
+            .returning(|_, _, _| {
+                Ok(vec![
+                    stored(1, "Whitby", "Oshawa", at(2, 9), "Iroquois"),
+                    stored(2, "Whitby", "Pickering", at(30, 17), "McKinney"),
+                ])
+            });
+        let games = vec![
+            incoming("Whitby", "Oshawa", at(2, 11), "McKinney"),
+            incoming("Whitby", "Ajax", at(4, 17), "McKinney"),
+        ];
+        let window = (at(1, 0), at(30, 0) + chrono::Duration::days(1));
+
+        // the game missing at the end of the month is left to a sync
+        let changes = import_changes(&repo, "whitby", window, &games).await;
+        assert_eq!(
+            vec![ChangeKind::Updated, ChangeKind::Inserted],
+            changes.iter().map(|c| c.kind).collect::<Vec<_>>()
+        );
+
+        // nothing scraped, nothing to compare
+        assert!(
+            import_changes(&repo, "whitby", window, &[])
+                .await
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_plan_sync() {
+        let mut removed = stored(5, "Whitby", "Uxbridge", at(6, 10), "Iroquois");
+        removed.deleted_at = Some(at(7, 0));
+        let existing = vec![
+            stored(1, "Whitby", "Ajax", at(1, 18), "Iroquois"),
+            stored(2, "Whitby", "Oshawa", at(2, 9), "Iroquois"),
+            stored(3, "Whitby", "Pickering", at(3, 17), "McKinney"),
+            // the old row of a game moved before syncs
+            stored(4, "Whitby", "Ajax", at(1, 16), "Iroquois"),
+            removed,
+        ];
+        let games = vec![
+            incoming("Whitby", "Ajax", at(1, 18), "Iroquois"),
+            incoming("Whitby", "Oshawa", at(2, 11), "McKinney"),
+            incoming("Whitby", "Oshawa", at(2, 11), "McKinney"),
+            incoming("Whitby", "Clarington", at(4, 12), "McKinney"),
+            // back on the schedule
+            incoming("Whitby", "Uxbridge", at(6, 10), "Iroquois"),
+        ];
+
+        let plan = plan_sync(&existing, &games);
+        assert_eq!(4, plan.live);
+        assert_eq!(vec![3], plan.insert);
+        assert_eq!(vec![(5, 4), (2, 1)], plan.update);
+        assert_eq!(vec![3, 4], plan.remove);
+
+        let kinds: Vec<(ChangeKind, &str)> = plan
+            .changes
+            .iter()
+            .map(|c| (c.kind, c.guest_team.as_str()))
+            .collect();
+        assert_eq!(
+            vec![
+                (ChangeKind::Inserted, "Uxbridge"),
+                (ChangeKind::Updated, "Oshawa"),
+                (ChangeKind::Inserted, "Clarington"),
+                (ChangeKind::Cancelled, "Pickering"),
+            ],
+            kinds
+        );
+        assert!(plan.check_cancellations(0.25).is_ok());
+    }
+
+    #[test]
+    fn test_plan_sync_compares_rows_like_the_unique_key() {
+        let mut removed = stored(1, "Whitby", "Ajax", at(1, 18), "Iroquois");
+        removed.deleted_at = Some(at(1, 0));
+        let existing = vec![removed];
+        // relisted in capitals, and once more from the other team's page
+        let games = vec![
+            incoming("WHITBY", "Ajax", at(1, 18), "Iroquois"),
+            incoming("Whitby ", "ajax", at(1, 18), "Iroquois"),
+        ];
+
+        let plan = plan_sync(&existing, &games);
+        assert!(plan.insert.is_empty());
+        assert_eq!(vec![(1, 0)], plan.update);
+        assert_eq!(
+            vec![ChangeKind::Inserted],
+            plan.changes.iter().map(|c| c.kind).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_plan_sync_of_truncated_scrape() {
+        let existing: Vec<Event> = (1..=8)
+            .map(|day| stored(day.into(), "Whitby", "Ajax", at(day, 18), "Iroquois"))
+            .collect();
+        let games = vec![incoming("Whitby", "Ajax", at(1, 18), "Iroquois")];
+
+        let plan = plan_sync(&existing, &games);
+        assert_eq!(7, plan.remove.len());
+        assert!(plan.check_cancellations(0.25).is_err());
+        assert!(plan.check_cancellations(1.0).is_ok());
+    }
+}
@@ -1,29 +1,5 @@
 // @generated automatically by Diesel CLI.
 
-diesel::table! {
-    #[allow(non_snake_case)]
-    RAMP_Locations (RARID) {
-        RARID -> Integer,
-        name -> Nullable<Text>,
-        abbr -> Nullable<Text>,
-        address -> Nullable<Text>,
-        city -> Nullable<Text>,
-        prov -> Nullable<Text>,
-        #[max_length = 64]
-        province_name -> Nullable<Varchar>,
-        pcode -> Nullable<Text>,
-        country -> Nullable<Text>,
-        phone -> Nullable<Text>,
-        liveBarnId -> Nullable<Text>,
-        #[max_length = 128]
-        loc -> Nullable<Varchar>,
-        location_id -> Integer,
-        #[max_length = 128]
-        match_type -> Nullable<Varchar>,
-        surface_id -> Nullable<Integer>,
-    }
-}
-
 diesel::table! {
     events (id) {
         id -> Unsigned<Bigint>,
@@ -47,46 +23,16 @@ diesel::table! {
         location_id -> Nullable<Integer>,
         surface_id -> Integer,
         date_created -> Timestamp,
-    }
-}
-
-diesel::table! {
-    feed_modes (id) {
-        id -> Integer,
-        #[max_length = 64]
-        feed_mode -> Varchar,
-    }
-}
-
-diesel::table! {
-    gamesheet_schedules (id) {
-        id -> Unsigned<Bigint>,
-        season_id -> Unsigned<Integer>,
-        game_data -> Json,
-        created_at -> Nullable<Timestamp>,
-        updated_at -> Nullable<Timestamp>,
-    }
-}
-
-diesel::table! {
-    gamesheet_seasons (id) {
-        id -> Unsigned<Integer>,
-        #[max_length = 200]
-        title -> Nullable<Varchar>,
-        #[max_length = 100]
-        site -> Varchar,
-        league_id -> Nullable<Unsigned<Integer>>,
-        is_active -> Nullable<Tinyint>,
-        start_date -> Nullable<Date>,
-        end_date -> Nullable<Date>,
-    }
-}
-
-diesel::table! {
-    gthl_mappings (location) {
-        #[max_length = 64]
-        location -> Varchar,
-        surface_id -> Integer,
+        #[max_length = 16]
+        age_group -> Nullable<Varchar>,
+        #[max_length = 16]
+        tier -> Nullable<Varchar>,
+        #[max_length = 16]
+        gender -> Nullable<Varchar>,
+        #[max_length = 16]
+        season_type -> Nullable<Varchar>,
+        canonical_id -> Nullable<Unsigned<Bigint>>,
+        deleted_at -> Nullable<Timestamp>,
     }
 }
 
@@ -119,36 +65,6 @@ diesel::table! {
     }
 }
 
-diesel::table! {
-    mhl_mappings (location) {
-        #[max_length = 64]
-        location -> Varchar,
-        surface_id -> Integer,
-    }
-}
-
-diesel::table! {
-    nyhl_mappings (location) {
-        #[max_length = 64]
-        location -> Varchar,
-        surface_id -> Integer,
-    }
-}
-
-diesel::table! {
-    ohf_teams (team_number) {
-        #[max_length = 128]
-        team_number -> Varchar,
-        team_name -> Nullable<Text>,
-        team_organization -> Nullable<Text>,
-        team_organization_path -> Nullable<Text>,
-        team_gender_identity -> Nullable<Text>,
-        division_name -> Nullable<Text>,
-        registrations_class_name -> Nullable<Text>,
-        category_name -> Nullable<Text>,
-    }
-}
-
 diesel::table! {
     provinces (id) {
         id -> Integer,
@@ -160,32 +76,18 @@ diesel::table! {
 }
 
 diesel::table! {
-    renditions (id) {
-        id -> Integer,
-        surface_id -> Integer,
-        #[max_length = 32]
-        name -> Varchar,
-        width -> Integer,
-        height -> Integer,
+    scrape_runs (id) {
+        id -> Unsigned<Bigint>,
+        #[max_length = 100]
+        site -> Varchar,
+        started_at -> Datetime,
+        finished_at -> Datetime,
+        games_found -> Integer,
+        parse_errors -> Integer,
         #[max_length = 16]
-        ratio -> Varchar,
-        bitrate -> Unsigned<Bigint>,
-    }
-}
-
-diesel::table! {
-    schema_migrations (version) {
-        version -> Bigint,
-        dirty -> Bool,
-    }
-}
-
-diesel::table! {
-    session (session_key) {
-        #[max_length = 64]
-        session_key -> Char,
-        session_data -> Nullable<Blob>,
-        session_expiry -> Unsigned<Integer>,
+        status -> Varchar,
+        error -> Nullable<Text>,
+        alert -> Nullable<Text>,
     }
 }
 
@@ -231,13 +133,6 @@ diesel::table! {
     }
 }
 
-diesel::table! {
-    surface_feed_modes (surface_id, feed_mode_id) {
-        surface_id -> Integer,
-        feed_mode_id -> Integer,
-    }
-}
-
 diesel::table! {
     surfaces (id) {
         id -> Integer,
@@ -260,58 +155,15 @@ diesel::table! {
     }
 }
 
-diesel::table! {
-    todb_surfaces (id) {
-        id -> Integer,
-        #[max_length = 256]
-        fullname -> Nullable<Varchar>,
-        #[max_length = 256]
-        fullshortname -> Nullable<Varchar>,
-        #[max_length = 256]
-        street -> Nullable<Varchar>,
-        #[max_length = 256]
-        city -> Nullable<Varchar>,
-        #[max_length = 45]
-        province -> Nullable<Varchar>,
-    }
-}
-
-diesel::table! {
-    users (username) {
-        #[max_length = 16]
-        username -> Varchar,
-        #[max_length = 64]
-        password -> Varchar,
-        created_at -> Nullable<Timestamp>,
-        updated_at -> Nullable<Timestamp>,
-    }
-}
-
 diesel::joinable!(locations -> provinces (province_id));
-diesel::joinable!(renditions -> surfaces (surface_id));
-diesel::joinable!(surface_feed_modes -> feed_modes (feed_mode_id));
-diesel::joinable!(surface_feed_modes -> surfaces (surface_id));
 diesel::joinable!(surfaces -> locations (location_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
-    RAMP_Locations,
     events,
-    feed_modes,
-    gamesheet_schedules,
-    gamesheet_seasons,
-    gthl_mappings,
     locations,
-    mhl_mappings,
-    nyhl_mappings,
-    ohf_teams,
     provinces,
-    renditions,
-    schema_migrations,
-    session,
+    scrape_runs,
     sites_config,
     sites_locations,
-    surface_feed_modes,
     surfaces,
-    todb_surfaces,
-    users,
 );
@@ -0,0 +1,169 @@
+// Follows migrations/postgres, see mod.rs.
+
+diesel::table! {
+    events (id) {
+        id -> BigInt,
+        #[max_length = 64]
+        site -> Varchar,
+        #[max_length = 64]
+        source_type -> Nullable<Varchar>,
+        datetime -> Timestamp,
+        #[max_length = 128]
+        home_team -> Varchar,
+        #[max_length = 128]
+        oid_home -> Nullable<Varchar>,
+        #[max_length = 128]
+        guest_team -> Varchar,
+        #[max_length = 128]
+        oid_guest -> Nullable<Varchar>,
+        #[max_length = 128]
+        location -> Nullable<Varchar>,
+        #[max_length = 128]
+        division -> Nullable<Varchar>,
+        location_id -> Nullable<Integer>,
+        surface_id -> Integer,
+        date_created -> Timestamp,
+        #[max_length = 16]
+        age_group -> Nullable<Varchar>,
+        #[max_length = 16]
+        tier -> Nullable<Varchar>,
+        #[max_length = 16]
+        gender -> Nullable<Varchar>,
+        #[max_length = 16]
+        season_type -> Nullable<Varchar>,
+        canonical_id -> Nullable<BigInt>,
+        deleted_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    locations (id) {
+        id -> Integer,
+        address1 -> Nullable<Text>,
+        address2 -> Nullable<Text>,
+        #[max_length = 32]
+        city -> Nullable<Varchar>,
+        #[max_length = 64]
+        name -> Varchar,
+        #[max_length = 128]
+        uuid -> Nullable<Varchar>,
+        #[max_length = 32]
+        recording_hours_local -> Nullable<Varchar>,
+        #[max_length = 32]
+        postal_code -> Nullable<Varchar>,
+        all_sheets_count -> Nullable<Integer>,
+        longitude -> Nullable<Float>,
+        latitude -> Nullable<Float>,
+        logo_url -> Nullable<Text>,
+        province_id -> Nullable<Integer>,
+        #[max_length = 11]
+        venue_status -> Nullable<Varchar>,
+        #[max_length = 32]
+        zone -> Nullable<Varchar>,
+        total_surfaces -> Nullable<Integer>,
+        deleted_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    provinces (id) {
+        id -> Integer,
+        #[max_length = 32]
+        province_name -> Varchar,
+        #[max_length = 32]
+        country -> Varchar,
+    }
+}
+
+diesel::table! {
+    scrape_runs (id) {
+        id -> BigInt,
+        #[max_length = 100]
+        site -> Varchar,
+        started_at -> Timestamp,
+        finished_at -> Timestamp,
+        games_found -> Integer,
+        parse_errors -> Integer,
+        #[max_length = 16]
+        status -> Varchar,
+        error -> Nullable<Text>,
+        alert -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    sites_config (id) {
+        id -> Integer,
+        #[max_length = 100]
+        site_name -> Varchar,
+        #[max_length = 200]
+        display_name -> Nullable<Varchar>,
+        #[max_length = 500]
+        base_url -> Varchar,
+        #[max_length = 100]
+        home_team -> Nullable<Varchar>,
+        parser_config -> Nullable<Json>,
+        enabled -> Nullable<Bool>,
+        last_scraped_at -> Nullable<Timestamp>,
+        scrape_frequency_hours -> Nullable<Integer>,
+        notes -> Nullable<Text>,
+        created_at -> Nullable<Timestamp>,
+        updated_at -> Nullable<Timestamp>,
+        #[max_length = 64]
+        parser_type -> Varchar,
+    }
+}
+
+diesel::table! {
+    sites_locations (site, location) {
+        #[max_length = 64]
+        site -> Varchar,
+        #[max_length = 128]
+        location -> Varchar,
+        location_id -> Nullable<Integer>,
+        #[max_length = 64]
+        loc -> Nullable<Varchar>,
+        #[max_length = 64]
+        surface -> Nullable<Varchar>,
+        #[max_length = 128]
+        address -> Nullable<Varchar>,
+        #[max_length = 32]
+        match_type -> Nullable<Varchar>,
+        surface_id -> Integer,
+    }
+}
+
+diesel::table! {
+    surfaces (id) {
+        id -> Integer,
+        location_id -> Integer,
+        #[max_length = 64]
+        name -> Varchar,
+        #[max_length = 128]
+        uuid -> Varchar,
+        orderIndex -> Integer,
+        venue_id -> Integer,
+        closed_from -> BigInt,
+        coming_soon -> Bool,
+        online -> Bool,
+        #[max_length = 32]
+        status -> Varchar,
+        #[max_length = 32]
+        sports -> Varchar,
+        first_media_date -> BigInt,
+        deleted_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::joinable!(locations -> provinces (province_id));
+diesel::joinable!(surfaces -> locations (location_id));
+
+diesel::allow_tables_to_appear_in_same_query!(
+    events,
+    locations,
+    provinces,
+    scrape_runs,
+    sites_config,
+    sites_locations,
+    surfaces,
+);
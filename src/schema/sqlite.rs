@@ -0,0 +1,131 @@
+// Follows migrations/sqlite, see mod.rs.
+
+diesel::table! {
+    events (id) {
+        id -> BigInt,
+        site -> Text,
+        source_type -> Nullable<Text>,
+        datetime -> Timestamp,
+        home_team -> Text,
+        oid_home -> Nullable<Text>,
+        guest_team -> Text,
+        oid_guest -> Nullable<Text>,
+        location -> Nullable<Text>,
+        division -> Nullable<Text>,
+        location_id -> Nullable<Integer>,
+        surface_id -> Integer,
+        date_created -> Timestamp,
+        age_group -> Nullable<Text>,
+        tier -> Nullable<Text>,
+        gender -> Nullable<Text>,
+        season_type -> Nullable<Text>,
+        canonical_id -> Nullable<BigInt>,
+        deleted_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    locations (id) {
+        id -> Integer,
+        address1 -> Nullable<Text>,
+        address2 -> Nullable<Text>,
+        city -> Nullable<Text>,
+        name -> Text,
+        uuid -> Nullable<Text>,
+        recording_hours_local -> Nullable<Text>,
+        postal_code -> Nullable<Text>,
+        all_sheets_count -> Nullable<Integer>,
+        longitude -> Nullable<Float>,
+        latitude -> Nullable<Float>,
+        logo_url -> Nullable<Text>,
+        province_id -> Nullable<Integer>,
+        venue_status -> Nullable<Text>,
+        zone -> Nullable<Text>,
+        total_surfaces -> Nullable<Integer>,
+        deleted_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    provinces (id) {
+        id -> Integer,
+        province_name -> Text,
+        country -> Text,
+    }
+}
+
+diesel::table! {
+    scrape_runs (id) {
+        id -> BigInt,
+        site -> Text,
+        started_at -> Timestamp,
+        finished_at -> Timestamp,
+        games_found -> Integer,
+        parse_errors -> Integer,
+        status -> Text,
+        error -> Nullable<Text>,
+        alert -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    sites_config (id) {
+        id -> Integer,
+        site_name -> Text,
+        display_name -> Nullable<Text>,
+        base_url -> Text,
+        home_team -> Nullable<Text>,
+        parser_config -> Nullable<Json>,
+        enabled -> Nullable<Bool>,
+        last_scraped_at -> Nullable<Timestamp>,
+        scrape_frequency_hours -> Nullable<Integer>,
+        notes -> Nullable<Text>,
+        created_at -> Nullable<Timestamp>,
+        updated_at -> Nullable<Timestamp>,
+        parser_type -> Text,
+    }
+}
+
+diesel::table! {
+    sites_locations (site, location) {
+        site -> Text,
+        location -> Text,
+        location_id -> Nullable<Integer>,
+        loc -> Nullable<Text>,
+        surface -> Nullable<Text>,
+        address -> Nullable<Text>,
+        match_type -> Nullable<Text>,
+        surface_id -> Integer,
+    }
+}
+
+diesel::table! {
+    surfaces (id) {
+        id -> Integer,
+        location_id -> Integer,
+        name -> Text,
+        uuid -> Text,
+        orderIndex -> Integer,
+        venue_id -> Integer,
+        closed_from -> BigInt,
+        coming_soon -> Bool,
+        online -> Bool,
+        status -> Text,
+        sports -> Text,
+        first_media_date -> BigInt,
+        deleted_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::joinable!(locations -> provinces (province_id));
+diesel::joinable!(surfaces -> locations (location_id));
+
+diesel::allow_tables_to_appear_in_same_query!(
+    events,
+    locations,
+    provinces,
+    scrape_runs,
+    sites_config,
+    sites_locations,
+    surfaces,
+);
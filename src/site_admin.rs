@@ -0,0 +1,149 @@
+// Checks for sites_config rows added or changed through the sites CLI and the admin API.
+// A new site is only saved after a dry-run scrape of the current month shows the parser
+// can read its calendar.
+
+use crate::models;
+use crate::site_scraper::{self, ScrapedGame};
+use anyhow::{Result, anyhow};
+
+/// how many games a dry run shows
+pub const PREVIEW_GAMES: usize = 10;
+
+pub fn validate_site_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.len() > 64 {
+        return Err(anyhow!("site name must be 1 to 64 characters"));
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+    {
+        return Err(anyhow!(
+            "site name may only contain lowercase letters, digits, - and _"
+        ));
+    }
+    Ok(())
+}
+
+/// Returns the url without a trailing slash, since the scraper appends paths to it.
+pub fn validate_base_url(base_url: &str) -> Result<String> {
+    let u = url::Url::parse(base_url.trim()).map_err(|e| anyhow!("invalid base url: {}", e))?;
+    if u.scheme() != "http" && u.scheme() != "https" {
+        return Err(anyhow!("base url must be http or https"));
+    }
+    if u.host_str().is_none() {
+        return Err(anyhow!("base url has no host"));
+    }
+    if u.query().is_some() || u.fragment().is_some() {
+        return Err(anyhow!("base url must not have a query or fragment"));
+    }
+    Ok(u.as_str().trim_end_matches('/').to_string())
+}
+
+pub fn validate_parser_type(parser_type: &str) -> Result<()> {
+    if !site_scraper::PARSER_TYPES.contains(&parser_type) {
+        return Err(anyhow!(
+            "unknown parser type {}, expected one of {}",
+            parser_type,
+            site_scraper::PARSER_TYPES.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+/// Validates a new site and normalizes its base url.
+pub fn validate_new_site(site: &mut models::NewSitesConfig) -> Result<()> {
+    validate_site_name(&site.site_name)?;
+    site.base_url = validate_base_url(&site.base_url)?;
+    validate_parser_type(&site.parser_type)
+}
+
+pub fn validate_changes(changes: &mut models::SitesConfigChanges) -> Result<()> {
+    if let Some(base_url) = &changes.base_url {
+        changes.base_url = Some(validate_base_url(base_url)?);
+    }
+    if let Some(parser_type) = &changes.parser_type {
+        validate_parser_type(parser_type)?;
+    }
+    Ok(())
+}
+
+/// An unsaved row, for dry runs before the site exists in the database.
+pub fn unsaved_config(site: &models::NewSitesConfig) -> models::SitesConfig {
+    models::SitesConfig {
+        id: 0,
+        site_name: site.site_name.clone(),
+        display_name: site.display_name.clone(),
+        base_url: site.base_url.clone(),
+        home_team: site.home_team.clone(),
+        parser_type: site.parser_type.clone(),
+        parser_config: site.parser_config.clone(),
+        enabled: site.enabled,
+        last_scraped_at: None,
+        scrape_frequency_hours: site.scrape_frequency_hours,
+        notes: site.notes.clone(),
+        created_at: None,
+        updated_at: None,
+    }
+}
+
+/// Scrapes the current month of `site` without importing anything.
+#[tracing::instrument(name = "dry_run", skip_all, fields(site_name = %site.site_name))]
+pub async fn dry_run(
+    scraper: &site_scraper::Scraper,
+    site: &models::SitesConfig,
+) -> Result<Vec<ScrapedGame>> {
+    if !site_scraper::is_scrapable(&site.parser_type) {
+        return Err(anyhow!(
+            "parser type {} is not scraped by this tool",
+            site.parser_type
+        ));
+    }
+    let today = chrono::prelude::Local::now().naive_local().date();
+    let mut games = scraper.process_site(site, today).await?.games;
+    games.sort_by_key(|g| g.date);
+    Ok(games)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_validate_base_url() {
+        assert_eq!(
+            "https://www.whitbyhockey.com",
+            validate_base_url("https://www.whitbyhockey.com/").unwrap()
+        );
+        assert_eq!(
+            "https://example.com/league",
+            validate_base_url(" https://example.com/league/ ").unwrap()
+        );
+        assert!(validate_base_url("ftp://example.com").is_err());
+        assert!(validate_base_url("example.com").is_err());
+        assert!(validate_base_url("https://example.com/?Month=1").is_err());
+    }
+
+    #[test]
+    fn test_validate_new_site() {
+        let mut site = models::NewSitesConfig {
+            site_name: "whitby".into(),
+            display_name: None,
+            base_url: "https://www.whitbyhockey.com/".into(),
+            home_team: None,
+            parser_type: "month_based".into(),
+            parser_config: None,
+            enabled: Some(true),
+            scrape_frequency_hours: None,
+            notes: None,
+        };
+        validate_new_site(&mut site).unwrap();
+        assert_eq!("https://www.whitbyhockey.com", site.base_url);
+
+        site.parser_type = "weekly".into();
+        assert!(validate_new_site(&mut site).is_err());
+
+        site.parser_type = "calendar".into();
+        site.site_name = "Whitby Hockey".into();
+        assert!(validate_new_site(&mut site).is_err());
+    }
+}
@@ -1,13 +1,22 @@
 use anyhow::{Context, Result};
-use chrono::NaiveDate;
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime};
 use scraper::{ElementRef, Html, Selector};
 use std::sync::{Arc, LazyLock};
+use tracing::{Instrument, debug, info, warn};
 
-use crate::client::HttpClient;
+use crate::fetcher::SharedFetcher;
 use crate::models;
 use crate::{address_fetcher, repository};
 pub mod month_based;
 
+/// Values of `sites_config.parser_type`. "calendar" sites are parsed from `/Calendar/`,
+/// "month_based" from `/Schedule/`, and "external" and "custom" sites are skipped.
+pub const PARSER_TYPES: [&str; 4] = ["calendar", "month_based", "external", "custom"];
+
+pub fn is_scrapable(parser_type: &str) -> bool {
+    parser_type != "external" && parser_type != "custom"
+}
+
 static DAY_DETAILS_SELECTOR: LazyLock<Selector> =
     LazyLock::new(|| Selector::parse("div.day-details").unwrap());
 
@@ -31,10 +40,20 @@ static GROUP_SELECTOR: LazyLock<Selector> =
 static ADDRESS_SELECTOR: LazyLock<Selector> =
     LazyLock::new(|| Selector::parse("div.bg_primary > div > div > div > h2 > small").unwrap());
 
+/// Games parsed from a schedule page, with the events that looked like games but could
+/// not be parsed. A rise in errors usually means the site changed its markup.
+#[derive(Debug, Default)]
+pub struct Parsed {
+    pub games: Vec<ScrapedGame>,
+    pub errors: Vec<String>,
+}
+
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct ScrapedGame {
     pub date: chrono::NaiveDateTime,
     pub site_name: String,
+    #[serde(skip)]
+    pub site_display_name: Option<String>,
     pub home_team: String,
     pub away_team: String,
     pub location: String,
@@ -45,7 +64,7 @@ pub struct ScrapedGame {
 }
 
 pub struct Scraper {
-    pub client: Arc<HttpClient>,
+    pub client: SharedFetcher,
     address_fetcher: Arc<address_fetcher::AddressFetcher>,
     repo: Arc<dyn repository::RepositoryOps + Send + Sync>,
     import_locations: bool,
@@ -53,7 +72,7 @@ pub struct Scraper {
 
 impl Scraper {
     pub fn new(
-        client: Arc<HttpClient>,
+        client: SharedFetcher,
         address_fetcher: Arc<address_fetcher::AddressFetcher>,
         repo: Arc<dyn repository::RepositoryOps + Send + Sync>,
         import_locations: bool,
@@ -70,48 +89,54 @@ impl Scraper {
         &self,
         site: &models::SitesConfig,
         from_date: NaiveDate,
-    ) -> Result<Vec<ScrapedGame>> {
-        println!("processing site {}", site.site_name);
-
-        let mm = from_date.format("%m").to_string();
-        let yyyy = from_date.format("%Y").to_string();
-
-        let mut url = site.base_url.clone();
-        let s = match site.parser_type.as_str() {
-            "month_based" => format!("/Schedule/?Month={}&Year={}", mm, yyyy),
-            _ => format!("/Calendar/?Month={}&Year={}", mm, yyyy),
-        };
-
-        url.push_str(s.as_str());
-        println!("scraping {}", url);
+    ) -> Result<Parsed> {
+        let url = schedule_url(site, from_date);
+        info!(url, "scraping");
         // Make HTTP GET request
         let contents = self.client.get_auto_redirect(&url).await?;
 
-        let mut games = match site.parser_type.as_str() {
-            "month_based" => month_based::parse_schedules(&site.site_name, contents, &mm, &yyyy)?,
-            _ => self.scrape_games(&site.site_name, &contents)?,
-        };
+        self.process_page(site, contents, from_date).await
+    }
 
-        println!("Scraped {} games from {}", games.len(), site.site_name);
+    /// Parses a schedule page that was already fetched, or read from disk, and resolves
+    /// the venue addresses of its games.
+    pub async fn process_page(
+        &self,
+        site: &models::SitesConfig,
+        contents: String,
+        from_date: NaiveDate,
+    ) -> Result<Parsed> {
+        let Parsed { mut games, errors } =
+            parse_page(&site.site_name, &site.parser_type, contents, from_date)?;
+
+        info!(
+            games = games.len(),
+            errors = errors.len(),
+            "parsed schedule"
+        );
 
         let mut tasks = tokio::task::JoinSet::new();
         for game in games.iter_mut() {
             let mut game = game.clone();
+            game.site_display_name = site.display_name.clone();
             let fetcher = Arc::clone(&self.address_fetcher);
             let site_name = site.site_name.clone();
             let base_url = site.base_url.clone();
 
-            tasks.spawn(async move {
-                let address = fetcher
-                    .get_address(&site_name, &base_url, &game.address_url)
-                    .await;
-
-                game.address = address.unwrap_or_else(|e| {
-                    eprintln!("{}", e);
-                    "".into()
-                });
-                game
-            });
+            tasks.spawn(
+                async move {
+                    let address = fetcher
+                        .get_address(&site_name, &base_url, &game.address_url)
+                        .await;
+
+                    game.address = address.unwrap_or_else(|e| {
+                        warn!(url = game.address_url, error = %e, "failed to get address");
+                        "".into()
+                    });
+                    game
+                }
+                .in_current_span(),
+            );
         }
         let mut games: Vec<ScrapedGame> = Vec::with_capacity(games.len());
         while let Some(g) = tasks.join_next().await {
@@ -133,130 +158,11 @@ impl Scraper {
                 })
                 .collect();
 
-            let repo = Arc::clone(&self.repo);
-            tokio::task::spawn_blocking(move || {
-                repo.import_locations("", locations).unwrap();
-            })
-            .await?;
+            self.repo
+                .import_locations(&site.site_name, locations)
+                .await?;
         }
-        Ok(games)
-    }
-
-    pub fn scrape_games(&self, site_name: &str, contents: &str) -> Result<Vec<ScrapedGame>> {
-        let doc = Html::parse_document(contents);
-        let mut games: Vec<ScrapedGame> = Vec::new();
-
-        for ds in doc.select(&*DAY_DETAILS_SELECTOR) {
-            let id = ds
-                .attr("id")
-                .ok_or_else(|| anyhow::anyhow!("id not found"))?;
-
-            let id = id.replace("day-", "");
-            // println!("{}", id);
-
-            let dt = chrono::NaiveDate::parse_from_str(&id, "%b-%d-%Y")
-                .context("failed to parse date")?;
-
-            for item in ds.select(&*EVENT_LIST_SELECTOR) {
-                if item.text().any(|t| {
-                    let t = t.to_lowercase();
-                    t.contains("practice")
-                        || t.contains("tournament")
-                        || t.contains("all day")
-                        || t.contains("cancelled")
-                        || t.contains("time-secondary")
-                }) {
-                    continue;
-                }
-                let game = self.scrape_game(item, dt, site_name);
-                let game = match game {
-                    Ok(g) => g,
-                    Err(e) => {
-                        eprintln!("{}", e);
-                        continue;
-                    }
-                };
-
-                games.push(game);
-            }
-        }
-
-        Ok(games)
-    }
-
-    fn scrape_game(&self, item: ElementRef, dt: NaiveDate, site_name: &str) -> Result<ScrapedGame> {
-        let tt = item
-            .select(&*TIME_SELECTOR)
-            .next()
-            .context("time not found")?;
-
-        let ts = tt.text().next().context("time ts not found")?;
-        let tt = chrono::NaiveTime::parse_from_str(ts, "%I:%M %p").context("date not found")?;
-        let dt = dt.and_time(tt);
-
-        let subj_owner = item
-            .select(&*SUBJECT_OWNER_SELECTOR)
-            .next()
-            .context("subj owner not found")?;
-
-        let subj_text = item
-            .select(&*SUBJECT_TEXT_SELECTOR)
-            .next()
-            .context("subj text not found")?;
-
-        let subj_owner = subj_owner.text().next().unwrap();
-        let subj_text = subj_text.text().next().unwrap();
-
-        let home_team: String;
-        let away_team: String;
-        if subj_text.contains("@ ") {
-            home_team = subj_text.replace("@ ", "");
-            away_team = subj_owner.into();
-        } else {
-            home_team = subj_owner.into();
-            away_team = subj_text.replace("vs ", "").into();
-        }
-
-        let loc = item
-            .select(&*LOCATION_SELECTOR)
-            .next()
-            .context("location selector not found")?;
-
-        let loc = loc.text().next().unwrap();
-
-        let division = match item.select(&*GROUP_SELECTOR).next() {
-            Some(group) => group.text().next().unwrap(),
-            _ => subj_owner,
-        };
-
-        let address_node = item
-            .first_child()
-            .context("first child not found 1")?
-            .first_child()
-            .context("grand child not found 2")?
-            .children()
-            .nth(2)
-            .context("second node not found 3")?
-            .first_child()
-            .context("first child not found 4")?;
-
-        let address_element = address_node
-            .value()
-            .as_element()
-            .context("element not found")?;
-
-        let address_url = address_element.attr("href").context("href not found")?;
-
-        Ok(ScrapedGame {
-            site_name: site_name.into(),
-            date: dt,
-            division: division.into(),
-            home_team: home_team,
-            away_team,
-            location: loc.into(),
-            address_url: address_url.into(),
-            address: "".into(),
-        })
+        Ok(Parsed { games, errors })
     }
 
     pub fn scrape_local_address(&self, contents: &str) -> Result<String> {
@@ -297,28 +203,312 @@ impl Scraper {
     }
 }
 
+/// The schedule page of `site` for the month of `from_date`.
+pub fn schedule_url(site: &models::SitesConfig, from_date: NaiveDate) -> String {
+    let mm = from_date.format("%m");
+    let yyyy = from_date.format("%Y");
+    let path = match site.parser_type.as_str() {
+        "month_based" => format!("/Schedule/?Month={}&Year={}", mm, yyyy),
+        _ => format!("/Calendar/?Month={}&Year={}", mm, yyyy),
+    };
+    format!("{}{}", site.base_url, path)
+}
+
+/// The days a scrape of the schedule page for `from_date` covers in full, from the first
+/// of its month up to the first of the next. Calendar pages may show a few days around
+/// the month too, but not all their games.
+pub fn scraped_window(from_date: NaiveDate) -> (NaiveDateTime, NaiveDateTime) {
+    let first = from_date.with_day(1).unwrap();
+    let next = first.checked_add_months(chrono::Months::new(1)).unwrap();
+    (
+        first.and_time(NaiveTime::MIN),
+        next.and_time(NaiveTime::MIN),
+    )
+}
+
+/// `contents` cut down to the day blocks both parsers read, to keep a real page as a test
+/// fixture: each block keeps its markup as served, the rest of the page is dropped.
+pub fn trim_page(contents: &str) -> String {
+    let doc = Html::parse_document(contents);
+    let mut page = String::from("<!DOCTYPE html>\n<html><body>\n");
+    for ds in doc.select(&DAY_DETAILS_SELECTOR) {
+        // blocks inside another block are already kept with it
+        if ds
+            .ancestors()
+            .filter_map(ElementRef::wrap)
+            .any(|a| DAY_DETAILS_SELECTOR.matches(&a))
+        {
+            continue;
+        }
+        page.push_str(&ds.html());
+        page.push('\n');
+    }
+    page.push_str("</body></html>\n");
+    page
+}
+
+/// Parses a schedule page with the parser for `parser_type`. Month based pages only show
+/// the day, so the month and year come from `from_date`.
+pub fn parse_page(
+    site_name: &str,
+    parser_type: &str,
+    contents: String,
+    from_date: NaiveDate,
+) -> Result<Parsed> {
+    match parser_type {
+        "month_based" => {
+            let mm = from_date.format("%m").to_string();
+            let yyyy = from_date.format("%Y").to_string();
+            month_based::parse_schedules(site_name, contents, &mm, &yyyy)
+        }
+        _ => parse_calendar(site_name, &contents),
+    }
+}
+
+pub fn parse_calendar(site_name: &str, contents: &str) -> Result<Parsed> {
+    let doc = Html::parse_document(contents);
+    let mut games: Vec<ScrapedGame> = Vec::new();
+    let mut errors: Vec<String> = Vec::new();
+
+    for ds in doc.select(&*DAY_DETAILS_SELECTOR) {
+        let id = ds
+            .attr("id")
+            .ok_or_else(|| anyhow::anyhow!("id not found"))?;
+
+        let id = id.replace("day-", "");
+        // println!("{}", id);
+
+        let dt =
+            chrono::NaiveDate::parse_from_str(&id, "%b-%d-%Y").context("failed to parse date")?;
+
+        for item in ds.select(&*EVENT_LIST_SELECTOR) {
+            if item.text().any(|t| {
+                let t = t.to_lowercase();
+                t.contains("practice")
+                    || t.contains("tournament")
+                    || t.contains("all day")
+                    || t.contains("cancelled")
+                    || t.contains("time-secondary")
+            }) {
+                continue;
+            }
+            let game = scrape_game(item, dt, site_name);
+            let game = match game {
+                Ok(g) => g,
+                Err(e) => {
+                    debug!(error = %e, "failed to parse game");
+                    errors.push(e.to_string());
+                    continue;
+                }
+            };
+
+            games.push(game);
+        }
+    }
+
+    Ok(Parsed { games, errors })
+}
+
+fn scrape_game(item: ElementRef, dt: NaiveDate, site_name: &str) -> Result<ScrapedGame> {
+    let tt = item
+        .select(&*TIME_SELECTOR)
+        .next()
+        .context("time not found")?;
+
+    let ts = tt.text().next().context("time ts not found")?;
+    let tt = chrono::NaiveTime::parse_from_str(ts, "%I:%M %p").context("date not found")?;
+    let dt = dt.and_time(tt);
+
+    let subj_owner = item
+        .select(&*SUBJECT_OWNER_SELECTOR)
+        .next()
+        .context("subj owner not found")?;
+
+    let subj_text = item
+        .select(&*SUBJECT_TEXT_SELECTOR)
+        .next()
+        .context("subj text not found")?;
+
+    let subj_owner = subj_owner.text().next().unwrap();
+    let subj_text = subj_text.text().next().unwrap();
+
+    let home_team: String;
+    let away_team: String;
+    if subj_text.contains("@ ") {
+        home_team = subj_text.replace("@ ", "");
+        away_team = subj_owner.into();
+    } else {
+        home_team = subj_owner.into();
+        away_team = subj_text.replace("vs ", "").into();
+    }
+
+    let loc = item
+        .select(&*LOCATION_SELECTOR)
+        .next()
+        .context("location selector not found")?;
+
+    let loc = loc.text().next().unwrap();
+
+    let division = match item.select(&*GROUP_SELECTOR).next() {
+        Some(group) => group.text().next().unwrap(),
+        _ => subj_owner,
+    };
+
+    let address_node = item
+        .first_child()
+        .context("first child not found 1")?
+        .first_child()
+        .context("grand child not found 2")?
+        .children()
+        .nth(2)
+        .context("second node not found 3")?
+        .first_child()
+        .context("first child not found 4")?;
+
+    let address_element = address_node
+        .value()
+        .as_element()
+        .context("element not found")?;
+
+    let address_url = address_element.attr("href").context("href not found")?;
+
+    Ok(ScrapedGame {
+        site_name: site_name.into(),
+        site_display_name: None,
+        date: dt,
+        division: division.into(),
+        home_team: home_team,
+        away_team,
+        location: loc.into(),
+        address_url: address_url.into(),
+        address: "".into(),
+    })
+}
+
 #[cfg(test)]
 mod test {
+    use crate::address_fetcher::AddressFetcher;
+    use crate::client::Response;
+    use crate::fetcher::MockFetcher;
     use crate::repository::MockRepositoryOps;
-    use crate::{address_fetcher::AddressFetcher, client};
 
     use super::*;
     use std::fs;
     use std::sync::Arc;
 
+    fn scraper(fetcher: MockFetcher, repo: MockRepositoryOps, import_locations: bool) -> Scraper {
+        let fetcher = Arc::new(fetcher);
+        let address_fetcher = Arc::new(AddressFetcher::new(fetcher.clone()));
+        Scraper::new(fetcher, address_fetcher, Arc::new(repo), import_locations)
+    }
+
     #[test]
     fn test_scrape_remote_address() {
-        let client = Arc::new(client::HttpClient::new());
-        let fetcher = Arc::new(AddressFetcher::new(client.clone()));
-        let repo = Arc::new(MockRepositoryOps::new());
-        let sc = Scraper {
-            client: client.clone(),
-            address_fetcher: fetcher,
-            repo: repo,
-            import_locations: false,
-        };
-        let contents = fs::read_to_string("addr.html").unwrap();
+        let sc = scraper(MockFetcher::new(), MockRepositoryOps::new(), false);
+        let contents = fs::read_to_string(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/addr.html"
+        ))
+        .unwrap();
         let addr = sc.scrape_remote_address(&contents).unwrap();
         assert_eq!("728 Mountain St, Haliburton, ON  ", addr);
     }
+
+    #[test]
+    fn test_parse_calendar_counts_errors() {
+        // the second event has lost its time, as after a markup change
+        let page = "<div class=\"day-details\" id=\"day-Nov-08-2025\">\
+            <div class=\"event-list-item\"><div><div><div class=\"time-primary\">7:30 PM</div>\
+            <div class=\"subject-owner\">Whitby Wildcats</div><div class=\"location\">\
+            <a href=\"/Venue/1/\">Iroquois Park</a></div><div class=\"subject-text\">vs Ajax</div>\
+            </div></div></div>\
+            <div class=\"event-list-item\"><div><div><div class=\"time-new\">8:30 PM</div>\
+            <div class=\"subject-owner\">Whitby Wildcats</div><div class=\"location\">\
+            <a href=\"/Venue/1/\">Iroquois Park</a></div><div class=\"subject-text\">vs Oshawa</div>\
+            </div></div></div></div>";
+
+        let parsed = parse_calendar("whitby", page).unwrap();
+        assert_eq!(1, parsed.games.len());
+        assert_eq!(vec!["time not found".to_string()], parsed.errors);
+    }
+
+    #[tokio::test]
+    async fn test_process_site_with_canned_responses() {
+        let mut fetcher = MockFetcher::new();
+        fetcher
+            .expect_get_auto_redirect()
+            .withf(|url| url == "https://example.com/Calendar/?Month=11&Year=2025")
+            .times(1)
+            .returning(|_| {
+                Ok(fs::read_to_string(concat!(
+                    env!("CARGO_MANIFEST_DIR"),
+                    "/tests/fixtures/calendar/synthetic/missing_group.html"
+                ))?)
+            });
+        fetcher.expect_get().returning(|url| {
+            Ok(Response::Content(format!(
+                "<div class=\"callout\"><div><div><b>Venue</b>{}</div></div></div>",
+                url
+            )))
+        });
+        let mut repo = MockRepositoryOps::new();
+        repo.expect_import_locations()
+            .withf(|_, locations| locations.len() == 2)
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let site = models::SitesConfig {
+            id: 1,
+            site_name: "example".into(),
+            display_name: None,
+            base_url: "https://example.com".into(),
+            home_team: None,
+            parser_type: "calendar".into(),
+            parser_config: None,
+            enabled: Some(true),
+            last_scraped_at: None,
+            scrape_frequency_hours: None,
+            notes: None,
+            created_at: None,
+            updated_at: None,
+        };
+        let sc = scraper(fetcher, repo, true);
+        let mut games = sc
+            .process_site(&site, NaiveDate::from_ymd_opt(2025, 11, 1).unwrap())
+            .await
+            .unwrap()
+            .games;
+        games.sort_by_key(|g| g.date);
+
+        assert_eq!(2, games.len());
+        assert_eq!("https://example.com/Venue/1842/", games[0].address);
+        assert_eq!("https://example.com/Venue/2210/", games[1].address);
+    }
+
+    #[test]
+    fn test_trim_page() {
+        let page = "<html><head><script>track()</script></head><body><nav>Menu</nav>\
+            <div class=\"calendar-month\">\
+            <div class=\"day-details\" id=\"day-Nov-08-2025\"><div class=\"event-list-item\">\
+            <div class=\"time-primary\">7:30 PM</div></div></div>\
+            <div class=\"day-details\" id=\"day-Nov-09-2025\"></div></div>\
+            <footer>Footer</footer></body></html>";
+
+        let trimmed = trim_page(page);
+        assert!(!trimmed.contains("track()"));
+        assert!(!trimmed.contains("Menu"));
+        assert!(!trimmed.contains("Footer"));
+        assert!(trimmed.contains(
+            "<div class=\"day-details\" id=\"day-Nov-08-2025\"><div class=\"event-list-item\">\
+             <div class=\"time-primary\">7:30 PM</div></div></div>"
+        ));
+        assert_eq!(2, trimmed.matches("day-details").count());
+    }
+
+    #[test]
+    fn test_scraped_window() {
+        let (from, to) = scraped_window(NaiveDate::from_ymd_opt(2025, 12, 14).unwrap());
+        assert_eq!("2025-12-01 00:00:00", from.to_string());
+        assert_eq!("2026-01-01 00:00:00", to.to_string());
+    }
 }
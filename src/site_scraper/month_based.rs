@@ -1,8 +1,10 @@
 use anyhow::Result;
+use chrono::{Datelike, Months, NaiveDate, NaiveTime};
 // use diesel::prelude::MysqlConnection;
-use crate::site_scraper::ScrapedGame;
+use crate::site_scraper::{Parsed, ScrapedGame};
 use scraper::{ElementRef, Selector};
 use std::sync::LazyLock;
+use tracing::debug;
 
 static DAY_DETAILS_SELECTOR: LazyLock<Selector> =
     LazyLock::new(|| Selector::parse("div.day-details").unwrap());
@@ -27,102 +29,249 @@ static GROUP_SELECTOR: LazyLock<Selector> =
 static LOC_LINK_SELECTOR: LazyLock<Selector> =
     LazyLock::new(|| Selector::parse("div > div > div:nth-of-type(3) > a").unwrap());
 
-pub fn parse_schedules(
-    site: &str,
-    contents: String,
-    mm: &str,
-    yyyy: &str,
-) -> Result<Vec<ScrapedGame>> {
+pub fn parse_schedules(site: &str, contents: String, mm: &str, yyyy: &str) -> Result<Parsed> {
     let doc = scraper::Html::parse_document(contents.as_str());
 
+    let month = NaiveDate::parse_from_str(&format!("{}-{}-01", yyyy, mm), "%Y-%m-%d")?;
+
     let mut games: Vec<ScrapedGame> = Vec::new();
+    let mut errors: Vec<String> = Vec::new();
+    let mut listed = Vec::new();
     for dd in doc.select(&DAY_DETAILS_SELECTOR) {
         for item in dd.select(&EVENT_LIST_SELECTOR) {
-            let tt = item.select(&TIME_SELECTOR).next().unwrap();
-
-            let date = parse_datetime(tt, mm, yyyy);
-
-            let Some(sgroup) = item.select(&GROUP_SELECTOR).next() else {
-                continue;
-            };
-            let Some(sowner) = item.select(&SUBJECT_OWNER_SELECTOR).next() else {
+            // same as the calendar parser, cancelled games stay listed with a tag
+            if item.text().any(|t| t.to_lowercase().contains("cancelled")) {
                 continue;
-            };
-            let Some(stext) = item.select(&SUBJECT_TEXT_SELECTOR).next() else {
+            }
+            let Some(tt) = item.select(&TIME_SELECTOR).next() else {
+                errors.push("time not found".into());
                 continue;
             };
-            let Some(sloc) = item.select(&LOCATION_SELECTOR).next() else {
+
+            let Some((day, time)) = parse_day_time(tt) else {
+                debug!("date not found");
+                errors.push("date not found".into());
                 continue;
             };
+            listed.push((item, day, time));
+        }
+    }
 
-            let team1 = match sowner.text().next() {
-                Some(d) => d.to_string(),
-                _ => continue,
-            };
-            let team2 = match stext.text().next() {
-                Some(d) => d.to_string(),
-                _ => continue,
-            };
+    let days: Vec<u32> = listed.iter().map(|(_, day, _)| *day).collect();
+    let offsets = month_offsets(&days, days_in_month(month));
+    for ((item, day, time), offset) in listed.into_iter().zip(offsets) {
+        let item_month = if offset < 0 {
+            month - Months::new(offset.unsigned_abs())
+        } else {
+            month + Months::new(offset as u32)
+        };
+        let Some(date) = item_month.with_day(day).map(|d| d.and_time(time)) else {
+            let e = format!("invalid day {} in {}", day, item_month.format("%Y-%m"));
+            debug!("{}", e);
+            errors.push(e);
+            continue;
+        };
 
-            let teams: (String, String) = if team2.contains("@") {
-                (team2.replace("@ ", "").into(), team1.clone())
-            } else {
-                (team1.clone(), team2.replace("vs ", "").into())
-            };
+        let Some(sgroup) = item.select(&GROUP_SELECTOR).next() else {
+            continue;
+        };
+        let Some(sowner) = item.select(&SUBJECT_OWNER_SELECTOR).next() else {
+            continue;
+        };
+        let Some(stext) = item.select(&SUBJECT_TEXT_SELECTOR).next() else {
+            continue;
+        };
+        let Some(sloc) = item.select(&LOCATION_SELECTOR).next() else {
+            continue;
+        };
 
-            let division = match sgroup.text().next() {
-                Some(d) => d.to_string(),
-                _ => team1,
-            };
+        let team1 = match sowner.text().next() {
+            Some(d) => d.to_string(),
+            _ => continue,
+        };
+        let team2 = match stext.text().next() {
+            Some(d) => d.to_string(),
+            _ => continue,
+        };
 
-            let location = match sloc.text().next() {
-                Some(l) => l.to_string(),
-                _ => continue,
-            };
+        let teams: (String, String) = if team2.contains("@") {
+            (team2.replace("@ ", "").into(), team1.clone())
+        } else {
+            (team1.clone(), team2.replace("vs ", "").into())
+        };
 
-            let link = match item.select(&LOC_LINK_SELECTOR).next() {
-                Some(l) => l,
-                _ => {
-                    eprintln!("loc link not found");
-                    continue;
-                }
-            };
+        let division = match sgroup.text().next() {
+            Some(d) => d.to_string(),
+            _ => team1,
+        };
 
-            let address_url = match link.attr("href") {
-                Some(l) => l.to_string(),
-                _ => {
-                    eprintln!("loc link url not found");
-                    continue;
-                }
-            };
+        let location = match sloc.text().next() {
+            Some(l) => l.to_string(),
+            _ => continue,
+        };
+
+        let link = match item.select(&LOC_LINK_SELECTOR).next() {
+            Some(l) => l,
+            _ => {
+                debug!("loc link not found");
+                errors.push("loc link not found".into());
+                continue;
+            }
+        };
+
+        let address_url = match link.attr("href") {
+            Some(l) => l.to_string(),
+            _ => {
+                debug!("loc link url not found");
+                errors.push("loc link url not found".into());
+                continue;
+            }
+        };
 
-            games.push(ScrapedGame {
-                site_name: site.into(),
-                date: date,
-                division,
-                home_team: teams.0,
-                away_team: teams.1,
-                location,
-                address_url,
-                address: "".into(),
-            });
+        games.push(ScrapedGame {
+            site_name: site.into(),
+            site_display_name: None,
+            date: date,
+            division,
+            home_team: teams.0,
+            away_team: teams.1,
+            location,
+            address_url,
+            address: "".into(),
+        });
+    }
+    Ok(Parsed { games, errors })
+}
+
+fn days_in_month(first: NaiveDate) -> u32 {
+    (first + Months::new(1)).pred_opt().unwrap().day()
+}
+
+/// Which month each of `days`, the days of a page in order, falls in, relative to the
+/// month the page is for. The days only show the day of the month, and each time they go
+/// back the page moved on a month. The page's month is the run of days that reaches the
+/// middle of a month: runs before it are the last days of the previous month, runs after
+/// it the first days of the next. A page without such a run starts in its month, unless
+/// its first days don't fit in it.
+fn month_offsets(days: &[u32], month_len: u32) -> Vec<i32> {
+    let mut runs = Vec::with_capacity(days.len());
+    let mut run = 0;
+    for (i, &day) in days.iter().enumerate() {
+        if i > 0 && day < days[i - 1] {
+            run += 1;
         }
+        runs.push(run);
     }
-    Ok(games)
+
+    let in_run = |r: i32| days.iter().zip(&runs).filter(move |(_, run)| **run == r);
+    let own = (0..=run)
+        .find(|&r| in_run(r).any(|(&day, _)| (8..=21).contains(&day)))
+        .unwrap_or_else(|| {
+            let first_fits = in_run(0).all(|(&day, _)| day <= month_len);
+            if run > 0 && !first_fits { 1 } else { 0 }
+        });
+    runs.into_iter().map(|r| r - own).collect()
+}
+
+// "Sat 8" followed by "7:30 PM"
+fn parse_day_time(node: ElementRef) -> Option<(u32, NaiveTime)> {
+    let mut text = node.text();
+    let day = text.next()?.split(" ").nth(1)?.trim().parse().ok()?;
+    let time = NaiveTime::parse_from_str(text.next()?.trim(), "%I:%M %p").ok()?;
+    Some((day, time))
 }
 
-fn parse_datetime(node: ElementRef, mm: &str, yyyy: &str) -> chrono::NaiveDateTime {
-    let t = node.text().nth(1).unwrap();
-    let dt = node.text().nth(0).unwrap();
-    let day = dt.split(" ").nth(1).unwrap();
-    let mut dt_str = yyyy.to_string();
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn item(day_time: &str, text: &str) -> String {
+        format!(
+            "<div class=\"event-list-item\"><div><div class=\"time-primary\">{}</div>\
+             <div class=\"subject-group\">U13 A</div><div class=\"location\">\
+             <a href=\"/Venue/1901/\">Ajax Community Centre</a></div>\
+             <div class=\"subject-owner\">Ajax Knights</div>\
+             <div class=\"subject-text\">{}</div></div></div>",
+            day_time, text
+        )
+    }
+
+    fn parse(items: &[String]) -> Parsed {
+        let page = format!("<div class=\"day-details\">{}</div>", items.concat());
+        parse_schedules("ajax", page, "11", "2025").unwrap()
+    }
+
+    #[test]
+    fn test_parse_day_time() {
+        let day_time = |html: &str| {
+            let doc = scraper::Html::parse_fragment(html);
+            let node = doc.select(&TIME_SELECTOR).next().unwrap();
+            parse_day_time(node)
+        };
+        assert_eq!(
+            Some((8, NaiveTime::from_hms_opt(19, 30, 0).unwrap())),
+            day_time("<div class=\"time-primary\">Sat 8<span>7:30 PM</span></div>")
+        );
+        assert_eq!(None, day_time("<div class=\"time-primary\">Sat 8</div>"));
+        assert_eq!(
+            None,
+            day_time("<div class=\"time-primary\">Sat<span>7:30 PM</span></div>")
+        );
+        assert_eq!(
+            None,
+            day_time("<div class=\"time-primary\">Sat 8<span>TBA</span></div>")
+        );
+    }
+
+    #[test]
+    fn test_parse_schedules_skips_cancelled_games() {
+        let parsed = parse(&[
+            item("Sat 8<span>7:30 PM</span>", "vs Whitby Wildcats"),
+            item(
+                "Sun 9<span>6:00 PM</span>",
+                "vs Oshawa Generals - CANCELLED",
+            ),
+        ]);
+        assert_eq!(1, parsed.games.len());
+        assert_eq!("Whitby Wildcats", parsed.games[0].away_team);
+        assert!(parsed.errors.is_empty());
+    }
 
-    dt_str.push_str("-");
-    dt_str.push_str(mm.to_string().as_str());
-    dt_str.push_str("-");
-    dt_str.push_str(day);
-    dt_str.push_str(" ");
-    dt_str.push_str(t);
+    #[test]
+    fn test_parse_schedules_reports_bad_dates() {
+        let parsed = parse(&[
+            item("Sat 8<span>7:30 PM</span>", "vs Whitby Wildcats"),
+            item("Sun<span>6:00 PM</span>", "vs Oshawa Generals"),
+            item("Mon 31<span>6:00 PM</span>", "vs Pickering Panthers"),
+        ]);
+        assert_eq!(1, parsed.games.len());
+        assert_eq!(
+            vec!["date not found", "invalid day 31 in 2025-11"],
+            parsed.errors
+        );
+    }
 
-    chrono::NaiveDateTime::parse_from_str(dt_str.as_str(), "%Y-%m-%d %I:%M %p").unwrap()
+    #[test]
+    fn test_month_offsets() {
+        // the first days of the next month
+        assert_eq!(vec![0, 0, 1, 1], month_offsets(&[28, 30, 1, 3], 30));
+        // the last days of the previous month, then the whole month
+        assert_eq!(
+            vec![-1, -1, 0, 0, 0, 0],
+            month_offsets(&[30, 31, 1, 8, 15, 30], 30)
+        );
+        assert_eq!(vec![-1, 0, 0, 1], month_offsets(&[31, 2, 14, 1], 30));
+        assert_eq!(vec![0, 0, 0], month_offsets(&[1, 8, 15], 30));
+        // no day in the middle, but the 31st is not in a 30 day month
+        assert_eq!(vec![-1, 0], month_offsets(&[31, 2], 30));
+        assert!(month_offsets(&[], 30).is_empty());
+    }
+
+    #[test]
+    fn test_days_in_month() {
+        let first = |y, m| NaiveDate::from_ymd_opt(y, m, 1).unwrap();
+        assert_eq!(30, days_in_month(first(2025, 11)));
+        assert_eq!(31, days_in_month(first(2025, 12)));
+        assert_eq!(29, days_in_month(first(2024, 2)));
+    }
 }
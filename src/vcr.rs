@@ -0,0 +1,229 @@
+// Record and replay of HTTP traffic, to reproduce a failed scrape exactly. With a
+// `Recorder` set, `HttpClient` saves every attempt of a request, failed ones too, as one
+// JSON file per request holding the latest attempt, and `ReplayFetcher` serves those
+// files back without any network access. A request whose last attempt failed fails
+// again on replay.
+//
+// Requests without and with redirect following are recorded separately since they
+// answer differently for the same url, as "get_<key>.json" and "auto_<key>.json" where
+// the key comes from `saved_pages::unique_url_key`, so urls that flatten alike still get
+// their own file.
+
+use crate::client::Response;
+use crate::fetcher::Fetcher;
+use crate::saved_pages::unique_url_key;
+use anyhow::{Context, Result, anyhow};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RequestKind {
+    /// `Fetcher::get`, redirects are returned
+    Get,
+    /// `Fetcher::get_auto_redirect`
+    Auto,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Recording {
+    pub kind: RequestKind,
+    pub url: String,
+    /// the url the body came from, after following redirects
+    pub final_url: String,
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    /// where a `Get` request redirects to, as returned to the scraper
+    pub redirect: Option<String>,
+    pub body: String,
+    pub recorded_at: chrono::NaiveDateTime,
+    /// 1 for the first try
+    #[serde(default)]
+    pub attempt: u32,
+    /// why the request failed without a response, or its body could not be read
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl Recording {
+    /// An attempt that got no response.
+    pub fn failed(kind: RequestKind, url: &str, error: String) -> Self {
+        Recording {
+            kind,
+            url: url.into(),
+            final_url: url.into(),
+            status: 0,
+            headers: Vec::new(),
+            redirect: None,
+            body: "".into(),
+            recorded_at: chrono::Utc::now().naive_utc(),
+            attempt: 0,
+            error: Some(error),
+        }
+    }
+
+    /// The error of a failed attempt, as `HttpClient` gave up with it.
+    pub fn failure(&self) -> Option<anyhow::Error> {
+        match &self.error {
+            Some(e) => Some(anyhow!("recorded failure for {}: {}", self.url, e)),
+            _ if self.status >= 500 => {
+                Some(anyhow!("recorded status {} for {}", self.status, self.url))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn new(kind: RequestKind, url: &str, response: &reqwest::Response) -> Self {
+        Recording {
+            kind,
+            url: url.into(),
+            final_url: response.url().to_string(),
+            status: response.status().as_u16(),
+            headers: response
+                .headers()
+                .iter()
+                .map(|(k, v)| (k.to_string(), String::from_utf8_lossy(v.as_bytes()).into()))
+                .collect(),
+            redirect: None,
+            body: "".into(),
+            recorded_at: chrono::Utc::now().naive_utc(),
+            attempt: 0,
+            error: None,
+        }
+    }
+}
+
+fn recording_path(dir: &Path, kind: RequestKind, url: &str) -> PathBuf {
+    let prefix = match kind {
+        RequestKind::Get => "get",
+        RequestKind::Auto => "auto",
+    };
+    dir.join(format!("{}_{}.json", prefix, unique_url_key(url)))
+}
+
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    dir: PathBuf,
+}
+
+impl Recorder {
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+        Ok(Recorder { dir })
+    }
+
+    pub fn record(&self, rec: &Recording) -> Result<()> {
+        let path = recording_path(&self.dir, rec.kind, &rec.url);
+        let json = serde_json::to_string_pretty(rec)?;
+        std::fs::write(&path, json).with_context(|| format!("failed to write {}", path.display()))
+    }
+}
+
+/// Serves recordings made by `Recorder`, a request that was not recorded is an error.
+pub struct ReplayFetcher {
+    dir: PathBuf,
+}
+
+impl ReplayFetcher {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        ReplayFetcher { dir: dir.into() }
+    }
+
+    pub fn load(&self, kind: RequestKind, url: &str) -> Result<Recording> {
+        let path = recording_path(&self.dir, kind, url);
+        let json = std::fs::read_to_string(&path)
+            .with_context(|| format!("no recording for {} in {}", url, path.display()))?;
+        Ok(serde_json::from_str(&json)?)
+    }
+}
+
+#[async_trait::async_trait]
+impl Fetcher for ReplayFetcher {
+    async fn get(&self, url: &str) -> Result<Response> {
+        let rec = self.load(RequestKind::Get, url)?;
+        if let Some(e) = rec.failure() {
+            return Err(e);
+        }
+        match rec.redirect {
+            Some(redirect) => Ok(Response::Redirect(redirect)),
+            _ => Ok(Response::Content(rec.body)),
+        }
+    }
+
+    async fn get_auto_redirect(&self, url: &str) -> Result<String> {
+        let rec = self.load(RequestKind::Auto, url)?;
+        if let Some(e) = rec.failure() {
+            return Err(e);
+        }
+        Ok(rec.body)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        let dir = std::env::temp_dir().join(format!("vcr-{}", std::process::id()));
+        let recorder = Recorder::new(&dir).unwrap();
+        let url = "https://example.com/Venue/Moved/";
+
+        let rec = Recording {
+            kind: RequestKind::Get,
+            url: url.into(),
+            final_url: url.into(),
+            status: 302,
+            headers: vec![("location".into(), "/Venue/1/".into())],
+            redirect: Some("https://example.com/Venue/1/".into()),
+            body: "".into(),
+            recorded_at: chrono::Utc::now().naive_utc(),
+            attempt: 1,
+            error: None,
+        };
+        recorder.record(&rec).unwrap();
+        recorder
+            .record(&Recording {
+                kind: RequestKind::Auto,
+                redirect: None,
+                status: 200,
+                body: "venue".into(),
+                ..rec.clone()
+            })
+            .unwrap();
+
+        let replay = ReplayFetcher::new(&dir);
+        assert_eq!(rec, replay.load(RequestKind::Get, url).unwrap());
+        assert!(matches!(
+            replay.get(url).await.unwrap(),
+            Response::Redirect(r) if r == "https://example.com/Venue/1/"
+        ));
+        assert_eq!("venue", replay.get_auto_redirect(url).await.unwrap());
+        assert!(replay.get("https://example.com/other").await.is_err());
+
+        // the last attempts failed
+        let down = "https://example.com/down";
+        recorder
+            .record(&Recording::failed(
+                RequestKind::Get,
+                down,
+                "connection refused".into(),
+            ))
+            .unwrap();
+        recorder
+            .record(&Recording {
+                kind: RequestKind::Auto,
+                url: down.into(),
+                status: 503,
+                ..rec.clone()
+            })
+            .unwrap();
+        let err = replay.get(down).await.unwrap_err();
+        assert!(err.to_string().contains("connection refused"));
+        let err = replay.get_auto_redirect(down).await.unwrap_err();
+        assert!(err.to_string().contains("503"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
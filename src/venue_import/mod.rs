@@ -1,8 +1,11 @@
+use crate::db::{self, DbConnection, UnsignedBig};
 use crate::models;
 use crate::schema;
+use anyhow::{Context, Result};
 use std::fs;
+use tracing::debug;
 
-use diesel::{RunQueryDsl, prelude::MysqlConnection};
+use diesel::{Connection, RunQueryDsl};
 
 pub fn parse_file(path: &str) -> Vec<models::LocationJson> {
     let contents = fs::read_to_string(path).unwrap();
@@ -12,7 +15,13 @@ pub fn parse_file(path: &str) -> Vec<models::LocationJson> {
     loc
 }
 
-pub fn import(conn: &mut MysqlConnection, locations: Vec<models::LocationJson>) {
+/// Imports the venues with their provinces and surfaces in one transaction, `chunk_size`
+/// rows per statement.
+pub fn import(
+    conn: &mut DbConnection,
+    locations: Vec<models::LocationJson>,
+    chunk_size: usize,
+) -> Result<()> {
     let mut loc_inserts: Vec<models::Location> = Vec::with_capacity(100);
     let mut provinces: Vec<models::Provnice> = Vec::new();
     let mut surfaces: Vec<models::Surface> = Vec::new();
@@ -33,13 +42,13 @@ pub fn import(conn: &mut MysqlConnection, locations: Vec<models::LocationJson>)
                 uuid: loc.uuid.clone(),
                 orderIndex: s.orderIndex,
                 venue_id: s.venueId,
-                closed_from: s.closedFrom.unwrap_or(0),
+                closed_from: s.closedFrom.unwrap_or(0) as UnsignedBig,
                 coming_soon: s.comingSoon,
                 online: s.online,
                 status: s.surfaceStatus.name,
                 sports: s.sports.iter().map(|s| s.name.clone()).collect(),
                 first_media_date: match s.firstMedia {
-                    Some(m) => m.firstMediaDate,
+                    Some(m) => m.firstMediaDate as UnsignedBig,
                     _ => 0,
                 },
             });
@@ -83,32 +92,23 @@ pub fn import(conn: &mut MysqlConnection, locations: Vec<models::LocationJson>)
             deleted_at: None,
         };
 
-        println!("{}", loc.id);
+        debug!(id = loc.id, "location");
 
         loc_inserts.push(loc);
     }
 
-    for c in provinces.chunks(100) {
-        diesel::insert_into(schema::provinces::table)
-            .values(c)
-            .on_conflict_do_nothing()
-            .execute(conn)
-            .unwrap();
-    }
-
-    for c in loc_inserts.chunks(100) {
-        diesel::insert_into(schema::locations::table)
-            .values(c)
-            .on_conflict_do_nothing()
-            .execute(conn)
-            .unwrap();
-    }
-
-    for c in surfaces.chunks(100) {
-        diesel::insert_into(schema::surfaces::table)
-            .values(c)
-            .on_conflict_do_nothing()
-            .execute(conn)
-            .unwrap();
-    }
+    let chunk_size = chunk_size.max(1);
+    conn.transaction(|conn| {
+        for c in provinces.chunks(chunk_size) {
+            db::insert_ignore!(schema::provinces::table, c, conn)?;
+        }
+        for c in loc_inserts.chunks(chunk_size) {
+            db::insert_ignore!(schema::locations::table, c, conn)?;
+        }
+        for c in surfaces.chunks(chunk_size) {
+            db::insert_ignore!(schema::surfaces::table, c, conn)?;
+        }
+        diesel::QueryResult::Ok(())
+    })
+    .context("failed to import venues")
 }
@@ -0,0 +1,246 @@
+// In-process mock of a league website for end-to-end scraper tests, served by axum on a
+// random local port. It serves:
+//   /Calendar/       calendar page, games at the local and remote venues below
+//   /Schedule/       month based schedule page with the same games
+//   /Venue/{id}/     local venue pages
+//   /Venue/Map/{id}  theonedb style remote venue page
+//   /Venue/Moved/    redirects to /Venue/1842/
+//   /Venue/Blocked/  redirects to the /Human/ captcha page
+//   /slow            answers after `SLOW_RESPONSE`
+//   /flaky           500 for the first `fail_times` requests, then 200
+//   /down            always 500
+// Every request is counted per path, so tests can check retries and caching.
+
+#![allow(dead_code)]
+
+use axum::Router;
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::get;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+pub const SLOW_RESPONSE: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Default)]
+struct SiteState {
+    requests: Arc<Mutex<HashMap<String, usize>>>,
+    flaky_failures: Arc<AtomicUsize>,
+    fail_times: usize,
+}
+
+pub struct MockSite {
+    pub base_url: String,
+    state: SiteState,
+}
+
+impl MockSite {
+    /// Starts a site whose /flaky path fails `fail_times` times before answering.
+    pub async fn start(fail_times: usize) -> MockSite {
+        let state = SiteState {
+            fail_times,
+            ..Default::default()
+        };
+
+        let app = Router::new()
+            .route("/Calendar/", get(calendar))
+            .route("/Schedule/", get(schedule))
+            .route("/Venue/Map/{id}", get(remote_venue))
+            .route("/Venue/Moved/", get(moved_venue))
+            .route("/Venue/Blocked/", get(blocked_venue))
+            .route("/Venue/{id}/", get(local_venue))
+            .route("/Human/", get(|| async { Html("are you human?") }))
+            .route("/slow", get(slow))
+            .route("/flaky", get(flaky))
+            .route("/down", get(down))
+            .layer(axum::middleware::from_fn_with_state(state.clone(), count))
+            .with_state(state.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        MockSite {
+            base_url: format!("http://{}", addr),
+            state,
+        }
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    /// Requests made for `path`, without the query string.
+    pub fn requests(&self, path: &str) -> usize {
+        let requests = self.state.requests.lock().unwrap();
+        requests.get(path).copied().unwrap_or(0)
+    }
+
+    pub fn site_config(&self, parser_type: &str) -> calendar_scraper::models::SitesConfig {
+        calendar_scraper::models::SitesConfig {
+            id: 1,
+            site_name: "mock".into(),
+            display_name: Some("Mock Minor Hockey".into()),
+            base_url: self.base_url.clone(),
+            home_team: None,
+            parser_type: parser_type.into(),
+            parser_config: None,
+            enabled: Some(true),
+            last_scraped_at: None,
+            scrape_frequency_hours: None,
+            notes: None,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+}
+
+async fn count(
+    State(state): State<SiteState>,
+    req: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> Response {
+    *state
+        .requests
+        .lock()
+        .unwrap()
+        .entry(req.uri().path().to_string())
+        .or_default() += 1;
+    next.run(req).await
+}
+
+// (day, time, subject owner, subject text, location, venue link)
+fn games(
+    headers: &HeaderMap,
+) -> Vec<(
+    u32,
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+    String,
+)> {
+    let remote = format!(
+        "http://{}/Venue/Map/10566?day=9&month=11&year=2025",
+        headers
+            .get(header::HOST)
+            .and_then(|h| h.to_str().ok())
+            .unwrap_or("localhost")
+    );
+    vec![
+        (
+            8,
+            "7:30 PM",
+            "Mock Wildcats U13 AA",
+            "vs Ajax Knights",
+            "Iroquois Park Arena - Pad 1",
+            "/Venue/1842/".into(),
+        ),
+        (
+            8,
+            "9:15 PM",
+            "Mock Wildcats U15 A",
+            "@ Oshawa Generals",
+            "Iroquois Park Arena - Pad 2",
+            "/Venue/1842/".into(),
+        ),
+        (
+            9,
+            "12:00 PM",
+            "Mock Wildcats U11 HL",
+            "vs Pickering Panthers",
+            "A.J. LaRue Arena",
+            remote,
+        ),
+        (
+            9,
+            "6:00 PM",
+            "Mock Wildcats U9 HL",
+            "vs Clarington Toros",
+            "McKinney Arena",
+            "/Venue/Moved/".into(),
+        ),
+    ]
+}
+
+async fn calendar(headers: HeaderMap) -> Html<String> {
+    let mut page = String::from("<html><body><div class=\"calendar-month\">");
+    for (day, time, owner, text, loc, href) in games(&headers) {
+        page.push_str(&format!(
+            "<div class=\"day-details\" id=\"day-Nov-{:02}-2025\"><div class=\"event-list-item\"><div><div>\
+             <div class=\"time-primary\">{}</div><div class=\"subject-owner\">{}</div>\
+             <div class=\"location\"><a href=\"{}\">{}</a></div><div class=\"subject-text\">{}</div>\
+             </div></div></div></div>",
+            day, time, owner, href, loc, text
+        ));
+    }
+    page.push_str("</div></body></html>");
+    Html(page)
+}
+
+async fn schedule(headers: HeaderMap) -> Html<String> {
+    let mut page = String::from("<html><body><div class=\"day-details\">");
+    for (day, time, owner, text, loc, href) in games(&headers) {
+        page.push_str(&format!(
+            "<div class=\"event-list-item\"><div><div class=\"time-primary\">Sat {}<span>{}</span></div>\
+             <div class=\"subject-group\">Minor</div><div class=\"location\"><a href=\"{}\">{}</a></div>\
+             <div class=\"subject-owner\">{}</div><div class=\"subject-text\">{}</div></div></div>",
+            day, time, href, loc, owner, text
+        ));
+    }
+    page.push_str("</div></body></html>");
+    Html(page)
+}
+
+async fn local_venue(Path(id): Path<String>) -> Response {
+    let address = match id.as_str() {
+        "1842" => "500 Victoria St W, Whitby, ON",
+        "1843" => "222 McKinney Dr, Whitby, ON",
+        _ => return StatusCode::NOT_FOUND.into_response(),
+    };
+    Html(format!(
+        "<html><body><div class=\"callout\"><div><div><b>Venue {}</b>{}</div></div></div></body></html>",
+        id, address
+    ))
+    .into_response()
+}
+
+async fn remote_venue() -> Html<String> {
+    let page = std::fs::read_to_string(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/addr.html"
+    ))
+    .unwrap();
+    Html(page)
+}
+
+async fn moved_venue() -> Response {
+    (StatusCode::FOUND, [(header::LOCATION, "/Venue/1843/")]).into_response()
+}
+
+async fn blocked_venue() -> Response {
+    (
+        StatusCode::FOUND,
+        [(header::LOCATION, "/Human/?return=/Venue/Blocked/")],
+    )
+        .into_response()
+}
+
+async fn slow() -> Html<&'static str> {
+    tokio::time::sleep(SLOW_RESPONSE).await;
+    Html("slow")
+}
+
+async fn flaky(State(state): State<SiteState>) -> Response {
+    if state.flaky_failures.fetch_add(1, Ordering::SeqCst) < state.fail_times {
+        return StatusCode::SERVICE_UNAVAILABLE.into_response();
+    }
+    Html("ok").into_response()
+}
+
+async fn down() -> Response {
+    StatusCode::INTERNAL_SERVER_ERROR.into_response()
+}
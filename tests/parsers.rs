@@ -0,0 +1,112 @@
+// Golden-file tests for the calendar and month based parsers. Every fixture
+// tests/fixtures/<parser>/<name>.html is parsed and compared with <name>.json next to it.
+// After an intended parser change, regenerate the snapshots and review the diff:
+//   UPDATE_GOLDEN=1 cargo test --test parsers
+//
+// The fixtures so far are hand-written, in <parser>/synthetic/, modelled on the markup of
+// the league sites. Real pages go in <parser>/ itself, trimmed to their day blocks, made
+// from a saved page or a --record recording with
+//   scrape fixture <page> --name <name> --parser <parser> --date YYYY-MM
+// which notes the month of the page in its first line.
+
+use calendar_scraper::site_scraper::{self, ScrapedGame};
+use std::path::{Path, PathBuf};
+
+// month based pages only show the day, pages without a "<!-- month: YYYY-MM" first line
+// are for this month
+const FIXTURE_MONTH: (i32, u32) = (2025, 11);
+
+#[derive(serde::Serialize)]
+struct Snapshot<'a> {
+    date: chrono::NaiveDateTime,
+    home_team: &'a str,
+    away_team: &'a str,
+    location: &'a str,
+    division: &'a str,
+    address_url: &'a str,
+}
+
+impl<'a> From<&'a ScrapedGame> for Snapshot<'a> {
+    fn from(g: &'a ScrapedGame) -> Self {
+        Snapshot {
+            date: g.date,
+            home_team: &g.home_team,
+            away_team: &g.away_team,
+            location: &g.location,
+            division: &g.division,
+            address_url: &g.address_url,
+        }
+    }
+}
+
+fn fixtures(parser_type: &str) -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(parser_type);
+    let mut files: Vec<PathBuf> = [dir.clone(), dir.join("synthetic")]
+        .iter()
+        .filter(|d| d.exists())
+        .flat_map(|d| std::fs::read_dir(d).unwrap())
+        .map(|e| e.unwrap().path())
+        .filter(|p| p.extension().is_some_and(|e| e == "html"))
+        .collect();
+    files.sort();
+    assert!(!files.is_empty(), "no fixtures in {}", dir.display());
+    files
+}
+
+fn fixture_month(contents: &str) -> chrono::NaiveDate {
+    let default = chrono::NaiveDate::from_ymd_opt(FIXTURE_MONTH.0, FIXTURE_MONTH.1, 1).unwrap();
+    contents
+        .strip_prefix("<!-- month: ")
+        .and_then(|rest| rest.get(..7))
+        .map(|ym| {
+            chrono::NaiveDate::parse_from_str(&format!("{}-01", ym), "%Y-%m-%d")
+                .unwrap_or_else(|e| panic!("bad fixture month {}: {}", ym, e))
+        })
+        .unwrap_or(default)
+}
+
+fn check_golden(parser_type: &str) {
+    let update = std::env::var("UPDATE_GOLDEN").is_ok();
+    let mut failed = Vec::new();
+
+    for html in fixtures(parser_type) {
+        let contents = std::fs::read_to_string(&html).unwrap();
+        let month = fixture_month(&contents);
+        let games = site_scraper::parse_page("fixture", parser_type, contents, month)
+            .unwrap_or_else(|e| panic!("{}: {:#}", html.display(), e))
+            .games;
+        let snapshot: Vec<Snapshot> = games.iter().map(Snapshot::from).collect();
+        let actual = serde_json::to_string_pretty(&snapshot).unwrap() + "\n";
+
+        let golden = html.with_extension("json");
+        if update {
+            std::fs::write(&golden, &actual).unwrap();
+            continue;
+        }
+        let expected = std::fs::read_to_string(&golden)
+            .unwrap_or_else(|e| panic!("{}: {}, run with UPDATE_GOLDEN=1", golden.display(), e));
+        if expected != actual {
+            eprintln!(
+                "{} does not match\n--- expected\n{}\n--- actual\n{}",
+                golden.display(),
+                expected,
+                actual
+            );
+            failed.push(golden);
+        }
+    }
+
+    assert!(failed.is_empty(), "golden files differ: {:?}", failed);
+}
+
+#[test]
+fn test_calendar_golden() {
+    check_golden("calendar");
+}
+
+#[test]
+fn test_month_based_golden() {
+    check_golden("month_based");
+}
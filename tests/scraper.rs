@@ -0,0 +1,216 @@
+// End-to-end tests of the client, address fetcher and scraper against the mock league
+// site in tests/mock_site, no network needed.
+
+mod mock_site;
+
+use calendar_scraper::address_fetcher::AddressFetcher;
+use calendar_scraper::client::{ClientOptions, HttpClient, Response};
+use calendar_scraper::fetcher::Fetcher;
+use calendar_scraper::repository::NoopRepository;
+use calendar_scraper::site_scraper::Scraper;
+use calendar_scraper::vcr::{Recorder, ReplayFetcher, RequestKind};
+use mock_site::MockSite;
+use std::sync::Arc;
+use std::time::Duration;
+
+fn client() -> Arc<HttpClient> {
+    Arc::new(HttpClient::with_options(ClientOptions {
+        timeout: Duration::from_secs(5),
+        max_retries: 3,
+        retry_delay: Duration::from_millis(10),
+        ..Default::default()
+    }))
+}
+
+fn scraper(client: calendar_scraper::fetcher::SharedFetcher) -> Scraper {
+    let fetcher = Arc::new(AddressFetcher::new(client.clone()));
+    Scraper::new(client, fetcher, Arc::new(NoopRepository), false)
+}
+
+fn november() -> chrono::NaiveDate {
+    chrono::NaiveDate::from_ymd_opt(2025, 11, 1).unwrap()
+}
+
+#[tokio::test]
+async fn test_process_site_resolves_addresses() {
+    let site = MockSite::start(0).await;
+    let sc = scraper(client());
+
+    let mut games = sc
+        .process_site(&site.site_config("calendar"), november())
+        .await
+        .unwrap()
+        .games;
+    games.sort_by_key(|g| g.date);
+
+    let addresses: Vec<&str> = games.iter().map(|g| g.address.as_str()).collect();
+    assert_eq!(
+        vec![
+            "500 Victoria St W, Whitby, ON",
+            "500 Victoria St W, Whitby, ON",
+            "728 Mountain St, Haliburton, ON  ",
+            // followed the redirect from /Venue/Moved/
+            "222 McKinney Dr, Whitby, ON",
+        ],
+        addresses
+    );
+    assert_eq!(
+        Some("Mock Minor Hockey"),
+        games[0].site_display_name.as_deref()
+    );
+    // two games at the same venue, fetched once
+    assert_eq!(1, site.requests("/Venue/1842/"));
+    assert_eq!(1, site.requests("/Calendar/"));
+}
+
+#[tokio::test]
+async fn test_process_site_month_based() {
+    let site = MockSite::start(0).await;
+    let sc = scraper(client());
+
+    let games = sc
+        .process_site(&site.site_config("month_based"), november())
+        .await
+        .unwrap()
+        .games;
+
+    assert_eq!(4, games.len());
+    assert_eq!(1, site.requests("/Schedule/"));
+    assert!(games.iter().all(|g| !g.address.is_empty()));
+}
+
+#[tokio::test]
+async fn test_get_returns_redirects() {
+    let site = MockSite::start(0).await;
+
+    match client().get(&site.url("/Venue/Moved/")).await.unwrap() {
+        Response::Redirect(url) => assert_eq!(site.url("/Venue/1843/"), url),
+        Response::Content(_) => panic!("expected a redirect"),
+    }
+}
+
+#[tokio::test]
+async fn test_captcha_redirect_is_an_error() {
+    let site = MockSite::start(0).await;
+    let fetcher = AddressFetcher::new(client());
+
+    let err = fetcher
+        .get_address("mock", &site.base_url, "/Venue/Blocked/")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("captcha"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_server_errors_are_retried() {
+    let site = MockSite::start(2).await;
+    let client = client();
+
+    let body = client.get_auto_redirect(&site.url("/flaky")).await.unwrap();
+    assert_eq!("ok", body);
+    assert_eq!(3, site.requests("/flaky"));
+}
+
+#[tokio::test]
+async fn test_gives_up_after_max_retries() {
+    let site = MockSite::start(0).await;
+
+    assert!(client().get(&site.url("/down")).await.is_err());
+    // the first attempt and three retries
+    assert_eq!(4, site.requests("/down"));
+}
+
+#[tokio::test]
+async fn test_slow_response_times_out() {
+    let site = MockSite::start(0).await;
+    let client = HttpClient::with_options(ClientOptions {
+        timeout: mock_site::SLOW_RESPONSE / 5,
+        max_retries: 1,
+        retry_delay: Duration::from_millis(10),
+        ..Default::default()
+    });
+
+    assert!(client.get_auto_redirect(&site.url("/slow")).await.is_err());
+    assert_eq!(2, site.requests("/slow"));
+    assert_eq!(
+        "slow",
+        self::client()
+            .get_auto_redirect(&site.url("/slow"))
+            .await
+            .unwrap()
+    );
+}
+
+#[tokio::test]
+async fn test_replay_reproduces_recorded_run() {
+    let site = MockSite::start(0).await;
+    let dir = std::env::temp_dir().join(format!("scraper-vcr-{}", std::process::id()));
+    let recording = HttpClient::with_options(ClientOptions {
+        retry_delay: Duration::from_millis(10),
+        ..Default::default()
+    })
+    .with_recorder(Recorder::new(&dir).unwrap());
+
+    let config = site.site_config("calendar");
+    let mut recorded = scraper(Arc::new(recording))
+        .process_site(&config, november())
+        .await
+        .unwrap()
+        .games;
+    let mut replayed = scraper(Arc::new(ReplayFetcher::new(&dir)))
+        .process_site(&config, november())
+        .await
+        .unwrap()
+        .games;
+    recorded.sort_by_key(|g| g.date);
+    replayed.sort_by_key(|g| g.date);
+
+    let addresses = |games: &[calendar_scraper::site_scraper::ScrapedGame]| {
+        games
+            .iter()
+            .map(|g| (g.date, g.home_team.clone(), g.address.clone()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(4, replayed.len());
+    assert_eq!(addresses(&recorded), addresses(&replayed));
+    // the replay did not touch the site
+    assert_eq!(1, site.requests("/Calendar/"));
+    assert_eq!(1, site.requests("/Venue/Moved/"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_replay_reproduces_failed_requests() {
+    let site = MockSite::start(0).await;
+    let dir = std::env::temp_dir().join(format!("scraper-vcr-failed-{}", std::process::id()));
+    let recording = HttpClient::with_options(ClientOptions {
+        max_retries: 1,
+        retry_delay: Duration::from_millis(10),
+        ..Default::default()
+    })
+    .with_recorder(Recorder::new(&dir).unwrap());
+    // nothing listens on a port just released
+    let closed = {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        format!("http://{}/", listener.local_addr().unwrap())
+    };
+
+    let down = site.url("/down");
+    assert!(recording.get(&down).await.is_err());
+    assert!(recording.get_auto_redirect(&down).await.is_err());
+    assert!(recording.get(&closed).await.is_err());
+
+    let replay = ReplayFetcher::new(&dir);
+    let rec = replay.load(RequestKind::Get, &down).unwrap();
+    assert_eq!((500, 2), (rec.status, rec.attempt));
+    let err = replay.get(&down).await.unwrap_err();
+    assert!(err.to_string().contains("500"), "{}", err);
+    let err = replay.get_auto_redirect(&down).await.unwrap_err();
+    assert!(err.to_string().contains("500"), "{}", err);
+    let rec = replay.load(RequestKind::Get, &closed).unwrap();
+    assert!(rec.error.is_some());
+    assert!(replay.get(&closed).await.is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}